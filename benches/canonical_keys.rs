@@ -0,0 +1,45 @@
+//! Compares the number of explored positions and the runtime of the search, depending on whether
+//! the transposition table shares entries between a position and its mirror image. See
+//! `Solver::set_canonical_keys`.
+//!
+//! Run with `cargo bench --bench canonical_keys`.
+
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::load;
+use connect_four_solver::{ConnectFour, Solver};
+
+fn main() {
+    let test_sets = [
+        "./tests/Test_L3_R1",
+        "./tests/Test_L2_R1",
+        "./tests/Test_L2_R2",
+        "./tests/Test_L1_R1",
+    ];
+    println!("test set            encode nodes      time        canonical nodes   time");
+    for test_set in test_sets {
+        let games = load(test_set);
+        let (encode_nodes, encode_time) = run(&games, false);
+        let (canonical_nodes, canonical_time) = run(&games, true);
+        println!(
+            "{test_set:<19} {encode_nodes:>15} {encode_time:>10.2?} {canonical_nodes:>17} \
+            {canonical_time:>10.2?}"
+        );
+    }
+}
+
+/// Scores every game with a fresh solver and verifies the result. Returns the total number of
+/// explored positions and the time it took.
+fn run(games: &[(ConnectFour, i8)], canonical_keys: bool) -> (u64, Duration) {
+    let mut nodes = 0;
+    let start = Instant::now();
+    for (game, expected) in games {
+        let mut solver = Solver::new();
+        solver.set_canonical_keys(canonical_keys);
+        assert_eq!(*expected, solver.score(game));
+        nodes += solver.nodes();
+    }
+    (nodes, start.elapsed())
+}
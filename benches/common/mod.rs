@@ -0,0 +1,14 @@
+//! Helpers shared by several benchmarks.
+
+use connect_four_solver::{read_dataset, ConnectFour};
+
+/// Positions of a test set, e.g. `"./tests/Test_L2_R1"`, together with their expected score.
+pub fn load(test_set: &str) -> Vec<(ConnectFour, i8)> {
+    read_dataset(test_set)
+        .unwrap()
+        .map(|entry| {
+            let (_, game, score) = entry.unwrap();
+            (game, score)
+        })
+        .collect()
+}
@@ -0,0 +1,44 @@
+//! Plays games to the end with perfect play, reusing one solver for all the moves of a game, like
+//! an engine would in a real match. Reports the number of explored positions and the runtime.
+//!
+//! The transposition table is kept small on purpose, so it fills up during a game and the
+//! replacement strategy matters.
+//!
+//! Run with `cargo bench --bench real_game`.
+
+mod common;
+
+use std::time::Instant;
+
+use common::load;
+use connect_four_solver::{ConnectFour, Solver};
+
+/// Number of games taken from the start of the test set.
+const NUM_GAMES: usize = 5;
+/// Prime, 16 Byte per bucket, about 128KiB.
+const CAPACITY: usize = 8191;
+
+fn main() {
+    let games: Vec<ConnectFour> = load("./tests/Test_L1_R2")
+        .into_iter()
+        .take(NUM_GAMES)
+        .map(|(game, _)| game)
+        .collect();
+
+    let mut nodes = 0;
+    let mut moves = 0;
+    let start = Instant::now();
+    let mut best_moves = Vec::new();
+    for mut game in games {
+        let mut solver = Solver::with_capacity(CAPACITY);
+        while !game.is_over() {
+            best_moves.clear();
+            solver.best_moves(&game, &mut best_moves);
+            game.play(best_moves[0]);
+            moves += 1;
+        }
+        nodes += solver.nodes();
+    }
+    let time = start.elapsed();
+    println!("{NUM_GAMES} games, {moves} moves, {nodes} nodes, {time:.2?}");
+}
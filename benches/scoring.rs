@@ -0,0 +1,69 @@
+//! Runtime of `Solver::score` and `Solver::best_moves` for positions from the beginning, the middle
+//! and the end of a game, taken from the test sets. Keep an eye on these to spot performance
+//! regressions. Criterion compares each run with the previous one and reports any change.
+//!
+//! Run with `cargo bench --bench scoring`.
+
+mod common;
+
+use std::hint::black_box;
+
+use common::load;
+use connect_four_solver::{ConnectFour, Solver};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+/// Number of positions taken from the start of each test set.
+const NUM_POSITIONS: usize = 20;
+
+const TEST_SETS: [(&str, &str); 3] = [
+    ("end", "./tests/Test_L3_R1"),
+    ("middle", "./tests/Test_L2_R1"),
+    ("begin", "./tests/Test_L1_R1"),
+];
+
+/// Scores [`NUM_POSITIONS`] positions of each phase, using a fresh solver for every position.
+fn score(c: &mut Criterion) {
+    let mut group = c.benchmark_group("score");
+    // Positions from the beginning of a game take a while, so keep the number of samples low.
+    group.sample_size(10);
+    for (phase, test_set) in TEST_SETS {
+        let games = positions(test_set);
+        group.bench_with_input(BenchmarkId::from_parameter(phase), &games, |b, games| {
+            b.iter(|| {
+                for (game, expected) in games {
+                    let mut solver = Solver::new();
+                    assert_eq!(*expected, solver.score(black_box(game)));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+/// Same as [`score`], but for [`Solver::best_moves`].
+fn best_moves(c: &mut Criterion) {
+    let mut group = c.benchmark_group("best_moves");
+    group.sample_size(10);
+    for (phase, test_set) in TEST_SETS {
+        let games = positions(test_set);
+        group.bench_with_input(BenchmarkId::from_parameter(phase), &games, |b, games| {
+            b.iter(|| {
+                for (game, _) in games {
+                    let mut solver = Solver::new();
+                    let mut best_moves = Vec::new();
+                    solver.best_moves(black_box(game), &mut best_moves);
+                    assert!(!best_moves.is_empty() || game.is_over());
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+/// The first [`NUM_POSITIONS`] positions of `test_set` together with their expected score.
+fn positions(test_set: &str) -> Vec<(ConnectFour, i8)> {
+    load(test_set).into_iter().take(NUM_POSITIONS).collect()
+}
+
+criterion_group!(benches, score, best_moves);
+criterion_main!(benches);
@@ -0,0 +1,56 @@
+//! Compares the number of explored positions and the runtime of the bisection driver used by
+//! `Solver::score` with the MTD(f) driver used by `Solver::score_mtdf`.
+//!
+//! Run with `cargo bench --bench search_drivers`.
+
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::load;
+use connect_four_solver::{ConnectFour, Solver};
+
+fn main() {
+    let test_sets = [
+        "./tests/Test_L3_R1",
+        "./tests/Test_L2_R1",
+        "./tests/Test_L2_R2",
+        "./tests/Test_L1_R1",
+    ];
+    println!(
+        "test set            bisection nodes   time        Mnodes/s    mtd(f) nodes   time        \
+        Mnodes/s"
+    );
+    for test_set in test_sets {
+        let games = load(test_set);
+        let (bisection_nodes, bisection_time) = run(&games, Solver::score);
+        let (mtdf_nodes, mtdf_time) = run(&games, Solver::score_mtdf);
+        println!(
+            "{test_set:<19} {bisection_nodes:>15} {bisection_time:>10.2?} {:>9.2} {mtdf_nodes:>15} \
+            {mtdf_time:>10.2?} {:>9.2}",
+            throughput(bisection_nodes, bisection_time),
+            throughput(mtdf_nodes, mtdf_time),
+        );
+    }
+}
+
+/// Million explored positions per second
+fn throughput(nodes: u64, time: Duration) -> f64 {
+    nodes as f64 / time.as_secs_f64() / 1e6
+}
+
+/// Scores every game with a fresh solver and verifies the result. Returns the total number of
+/// explored positions and the time it took.
+fn run(
+    games: &[(ConnectFour, i8)],
+    score: impl Fn(&mut Solver, &ConnectFour) -> i8,
+) -> (u64, Duration) {
+    let mut nodes = 0;
+    let start = Instant::now();
+    for (game, expected) in games {
+        let mut solver = Solver::new();
+        assert_eq!(*expected, score(&mut solver, game));
+        nodes += solver.nodes();
+    }
+    (nodes, start.elapsed())
+}
@@ -0,0 +1,61 @@
+//! Compares scoring the moves of a position in parallel with a transposition table for each thread
+//! (`Solver::best_moves_parallel`) against one table shared by all threads
+//! (`Solver::best_moves_shared`), and against scoring them one after another.
+//!
+//! Run with `cargo bench --bench shared_table --features rayon`.
+
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::load;
+use connect_four_solver::{Column, ConcurrentTranspositionTable, ConnectFour, Solver};
+
+/// Number of positions taken from the start of each test set.
+const NUM_POSITIONS: usize = 10;
+
+/// Same number of buckets as the table of `Solver::new`.
+const CAPACITY: usize = 4194301;
+
+fn main() {
+    let test_sets = [
+        ("end", "./tests/Test_L3_R1"),
+        ("middle", "./tests/Test_L2_R1"),
+        ("begin", "./tests/Test_L1_R1"),
+    ];
+    println!("{NUM_POSITIONS} positions per set, time per position");
+    println!("phase    serial       per thread   shared");
+    for (phase, test_set) in test_sets {
+        let games: Vec<ConnectFour> = load(test_set)
+            .into_iter()
+            .take(NUM_POSITIONS)
+            .map(|(game, _)| game)
+            .collect();
+        let serial = measure(&games, |solver, game, best_moves| {
+            solver.best_moves(game, best_moves)
+        });
+        let per_thread = measure(&games, |solver, game, best_moves| {
+            solver.best_moves_parallel(game, best_moves)
+        });
+        let shared = measure(&games, |solver, game, best_moves| {
+            let table = ConcurrentTranspositionTable::new(CAPACITY);
+            solver.best_moves_shared(game, &table, best_moves)
+        });
+        println!("{phase:<8} {serial:>10.2?} {per_thread:>12.2?} {shared:>10.2?}");
+    }
+}
+
+/// Average time `run` takes for each of the games, using a fresh solver every time.
+fn measure(
+    games: &[ConnectFour],
+    run: impl Fn(&mut Solver, &ConnectFour, &mut Vec<Column>),
+) -> Duration {
+    let start = Instant::now();
+    for game in games {
+        let mut solver = Solver::new();
+        let mut best_moves = Vec::new();
+        run(&mut solver, game, &mut best_moves);
+        assert!(!best_moves.is_empty() || game.is_over());
+    }
+    start.elapsed() / games.len() as u32
+}
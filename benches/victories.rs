@@ -0,0 +1,64 @@
+//! Compares checking many positions for a win one by one using `ConnectFour::is_victory` with the
+//! batched `ConnectFour::victories`. `is_victory` returns the result `play` remembered, while
+//! `victories` checks the bitboards of each position. See the `real_game` benchmark for what
+//! remembering costs `play` during a search.
+//!
+//! Run with `cargo bench --bench victories`.
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use connect_four_solver::ConnectFour;
+
+const NUM_POSITIONS: usize = 10_000;
+const REPETITIONS: u32 = 1_000;
+
+fn main() {
+    let games = random_positions(NUM_POSITIONS);
+
+    let one_by_one = measure(|| games.iter().filter(|game| game.is_victory()).count());
+    let batched = measure(|| ConnectFour::victories(&games).filter(|&win| win).count());
+
+    println!("{NUM_POSITIONS} positions, {REPETITIONS} repetitions");
+    println!("is_victory  {one_by_one:>10.2?}");
+    println!("victories   {batched:>10.2?}");
+}
+
+/// Total time of `REPETITIONS` calls to `count_wins`.
+fn measure(mut count_wins: impl FnMut() -> usize) -> Duration {
+    let expected = count_wins();
+    let start = Instant::now();
+    for _ in 0..REPETITIONS {
+        assert_eq!(expected, black_box(count_wins()));
+    }
+    start.elapsed()
+}
+
+/// Positions reached by playing a random number of random moves. Uses a fixed seed so runs are
+/// comparable.
+fn random_positions(num: usize) -> Vec<ConnectFour> {
+    // Xorshift, good enough to pick moves and avoids a dependency just for the benchmark.
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    (0..num)
+        .map(|_| {
+            let mut game = ConnectFour::new();
+            let num_moves = next() % 42;
+            for _ in 0..num_moves {
+                if game.is_over() {
+                    break;
+                }
+                let legal: Vec<_> = game.legal_moves().collect();
+                game.play(legal[next() as usize % legal.len()]);
+            }
+            game
+        })
+        .collect()
+}
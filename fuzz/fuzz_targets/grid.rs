@@ -0,0 +1,57 @@
+//! Feeds arbitrary grids to the board builder and to `ConnectFour::from_grid`. Neither must panic.
+//! The builder accepts any grid without floating stones, `from_grid` only boards which can be
+//! reached by legal play.
+
+#![no_main]
+
+use connect_four_solver::{ConnectFour, ConnectFourBuilder, Player};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: [u8; 42]| {
+    let grid: [[Option<Player>; 7]; 6] = std::array::from_fn(|row| {
+        std::array::from_fn(|column| match data[row * 7 + column] % 3 {
+            0 => None,
+            1 => Some(Player::One),
+            _ => Some(Player::Two),
+        })
+    });
+    let mut builder = ConnectFourBuilder::new();
+    for (row, cells) in grid.iter().enumerate() {
+        for (column, &player) in cells.iter().enumerate() {
+            builder = builder.cell(row as u8, column as u8, player);
+        }
+    }
+    if let Ok(game) = builder.build() {
+        assert_eq!(grid, game.to_grid());
+    }
+    if let Ok(game) = ConnectFour::from_grid(&grid) {
+        assert_eq!(grid, game.to_grid());
+        assert_reachable(&grid, &game);
+    }
+});
+
+/// One player has put in as many stones as the other one, or one more. That is player one, unless
+/// the players have been swapped. Every four in a row goes through the topmost stone of a column,
+/// which belongs to the player who moved last.
+fn assert_reachable(grid: &[[Option<Player>; 7]; 6], game: &ConnectFour) {
+    let count = |player| {
+        grid.iter()
+            .flatten()
+            .filter(|&&cell| cell == Some(player))
+            .count()
+    };
+    let (one, two) = (count(Player::One), count(Player::Two));
+    assert!(one.abs_diff(two) <= 1);
+    let last_player = if one > two { Player::One } else { Player::Two };
+    let may_be_last_stone = |&(row, column): &(u8, u8)| {
+        let on_top = row == 5 || grid[row as usize + 1][column as usize].is_none();
+        on_top && grid[row as usize][column as usize] == Some(last_player)
+    };
+    let lines = game.all_winning_lines();
+    if let Some(first) = lines.first() {
+        assert!(first
+            .iter()
+            .filter(|cell| may_be_last_stone(cell))
+            .any(|cell| lines.iter().all(|line| line.contains(cell))));
+    }
+}
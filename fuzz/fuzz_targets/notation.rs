@@ -0,0 +1,31 @@
+//! Feeds arbitrary strings to the parsers of move lists. They must never panic, and every game they
+//! accept must be reachable by legal play.
+
+#![no_main]
+
+use connect_four_solver::{ConnectFour, NotationFormat, PlayedGame};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(game) = PlayedGame::from_bytes(data) {
+        let mut replayed = ConnectFour::new();
+        for &column in game.moves() {
+            assert!(replayed.try_play(column).is_ok());
+        }
+        assert!(replayed == *game.board());
+    }
+    let Ok(notation) = std::str::from_utf8(data) else {
+        return;
+    };
+    let formats = [
+        NotationFormat::Packed1Indexed,
+        NotationFormat::Spaced1Indexed,
+        NotationFormat::Comma0Indexed,
+    ];
+    for format in formats {
+        if let Ok(game) = ConnectFour::from_notation(notation, format) {
+            // `from_key` rejects boards which can not be reached by legal play.
+            assert!(ConnectFour::from_key(game.encode()) == Ok(game));
+        }
+    }
+});
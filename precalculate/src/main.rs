@@ -1,48 +1,62 @@
 //! Precalculate the scores for starting postitions. See `precalculated.rs` for more information.
+//!
+//! The scores do not depend on the `scores.dat` the solver has been built with, so the file can be
+//! regenerated from scratch. Only the positions with the most stones are searched, without looking
+//! up precalculated scores. The scores of positions with fewer stones follow from the scores of
+//! their children.
 
 use std::{
+    collections::HashMap,
     fs::File,
     io::{BufWriter, Write},
 };
 
-use connect_four_solver::{score, ConnectFour};
-use rayon::iter::{IntoParallelRefIterator, ParallelExtend, ParallelIterator};
+use connect_four_solver::{unique_positions, Solver};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 
-const PRECALULATE_UP_TO_NUM_STONES: usize = 7;
+const PRECALULATE_UP_TO_NUM_STONES: u8 = 7;
 
 fn main() {
-    // Hold all unique game positions for `n` stones at index n.
-    let mut unique_boards: Vec<ConnectFour> = Vec::new();
-    let mut scores = Vec::new();
-
-    for num_stones in 0..PRECALULATE_UP_TO_NUM_STONES {
-        let mut new_boards = Vec::new();
-        for board in &unique_boards {
-            for col in board.legal_moves() {
-                let mut new_board = *board;
-                new_board.play(col);
-                new_boards.push(new_board);
-            }
-        }
-        // For the first iteration we start a new empty game.
-        if unique_boards.is_empty() {
-            new_boards.push(ConnectFour::new());
-        };
+    let mut scores: HashMap<u64, i8> = HashMap::new();
+
+    for num_stones in (0..PRECALULATE_UP_TO_NUM_STONES).rev() {
+        // A board and its mirror image count as the same position, since they share their score.
+        let unique_boards = unique_positions(num_stones, true);
         eprintln!(
-            "For {num_stones} stones: Checked {} permutations",
-            new_boards.len()
+            "For {num_stones} stones: Unique boards: {}",
+            unique_boards.len()
         );
-        new_boards.sort_by_key(ConnectFour::encode);
-        new_boards.dedup();
-        eprintln!("Unique boards: {}", new_boards.len());
-        unique_boards = new_boards;
 
         eprintln!("Calculating scores ...");
-        scores.par_extend(
+        let new_scores: Vec<(u64, i8)> = if num_stones + 1 == PRECALULATE_UP_TO_NUM_STONES {
+            // Each thread reuses its solver, so the transposition table carries over between
+            // positions.
             unique_boards
                 .par_iter()
-                .map(|board| (board.encode(), score(board))),
-        );
+                .map_init(Solver::without_book, |solver, board| {
+                    (board.canonical_encode(), solver.score(board))
+                })
+                .collect()
+        } else {
+            unique_boards
+                .iter()
+                .map(|board| {
+                    let score = if board.can_win_in_next_move() {
+                        // Scored without a search
+                        Solver::without_book().score(board)
+                    } else {
+                        // Nobody wins with fewer than seven stones, so every child has a score.
+                        board
+                            .children()
+                            .map(|(_, child)| -scores[&child.canonical_encode()])
+                            .max()
+                            .unwrap()
+                    };
+                    (board.canonical_encode(), score)
+                })
+                .collect()
+        };
+        scores.extend(new_scores);
     }
 
     eprintln!("NUM_STONES_PRECALCULATED: {PRECALULATE_UP_TO_NUM_STONES}");
@@ -51,6 +65,7 @@ fn main() {
     let file = File::create("scores.dat").unwrap();
     let mut out = BufWriter::new(file);
 
+    let mut scores: Vec<(u64, i8)> = scores.into_iter().collect();
     scores.sort_by_key(|(key, _)| *key);
 
     for (key, score) in scores {
@@ -1,6 +1,8 @@
 // Idea from: https://stackoverflow.com/questions/7033165/algorithm-to-check-a-connect-four-field
 
-/// Bitboard containing stones of one player:
+/// Bitboard containing stones of one player, on a board `W` columns wide and `H` rows high. Each
+/// column takes up `H + 1` bits. For the standard board with seven columns and six rows:
+///
 /// .  .  .  .  .  .  .  TOP
 /// 5 12 19 26 33 40 47
 /// 4 11 18 25 32 39 46
@@ -12,39 +14,48 @@
 ///
 /// `1` represents a stone of one player. `0` is an empty field, or a stone of the other player.
 #[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
-pub struct PlayerStones(u64);
+pub struct PlayerStones<const W: usize = 7, const H: usize = 6>(u64);
 
-impl PlayerStones {
+impl<const W: usize, const H: usize> PlayerStones<W, H> {
     /// Create empty Bitboard
-    pub fn new() -> PlayerStones {
+    pub fn new() -> Self {
         PlayerStones(0)
     }
 
     /// Tells if the board has a stone in the specified place. The bottom row and the leftmost
     /// column are `0`.
     pub fn is_empty(self, row: u8, column: u8) -> bool {
-        (cell(row, column) & self.0) == 0
+        (cell::<H>(row, column) & self.0) == 0
+    }
+
+    /// The same stones, plus one in each cell of `cells`.
+    pub fn with_stones(self, cells: u64) -> Self {
+        Self(self.0 | cells)
+    }
+
+    /// The same stones, minus the ones in `cells`.
+    pub fn without_stones(self, cells: u64) -> Self {
+        Self(self.0 & !cells)
     }
 
     /// Place a stone a the specified position
-    #[cfg(test)]
     pub fn place_stone(&mut self, row: u8, column: u8) {
-        self.0 |= cell(row, column)
+        self.0 |= cell::<H>(row, column)
     }
 
     pub fn is_win(self) -> bool {
-        let y = self.0 & (self.0 >> 6);
-        if (y & (y >> (2 * 6))) != 0 {
+        let y = self.0 & (self.0 >> H);
+        if (y & (y >> (2 * H))) != 0 {
             // check \ diagonal
             return true;
         }
-        let y = self.0 & (self.0 >> 7);
-        if (y & (y >> (2 * 7))) != 0 {
+        let y = self.0 & (self.0 >> (H + 1));
+        if (y & (y >> (2 * (H + 1)))) != 0 {
             // check horizontal
             return true;
         }
-        let y = self.0 & (self.0 >> 8);
-        if (y & (y >> (2 * 8))) != 0 {
+        let y = self.0 & (self.0 >> (H + 2));
+        if (y & (y >> (2 * (H + 2)))) != 0 {
             // check / diagonal
             return true;
         }
@@ -56,14 +67,62 @@ impl PlayerStones {
         false
     }
 
+    /// Number of stones of the player within `mask`.
+    pub fn stones_in(self, mask: u64) -> u32 {
+        (self.0 & mask).count_ones()
+    }
+
+    /// `true` if the player could still complete a four in a row, if they got to fill all the empty
+    /// cells. Also `true` if they already won.
+    pub fn can_still_win(self, mask: AllStones<W, H>) -> bool {
+        Self(self.0 | (full::<W, H>() & !mask.0)).is_win()
+    }
+
+    /// Bitmask of the cells which are part of a four in a row the player could still complete, if
+    /// they got to fill all the empty cells. Includes their stones in these lines.
+    pub fn live_cells(self, mask: AllStones<W, H>) -> u64 {
+        let open = self.0 | (full::<W, H>() & !mask.0);
+        let mut live = 0;
+        for shift in [1, H, H + 1, H + 2] {
+            // Lowest cell of each line in this direction. See `is_win`.
+            let y = open & (open >> shift);
+            let start = y & (y >> (2 * shift));
+            live |= start | start << shift | start << (2 * shift) | start << (3 * shift);
+        }
+        live
+    }
+
+    /// Same result as [`Self::is_win`], but checks all directions without early return. Slower for a
+    /// single board, yet easier to vectorize if called for many boards in a row.
+    pub fn is_win_branchless(self) -> bool {
+        let mut win = 0;
+        for shift in [1, H, H + 1, H + 2] {
+            // Vertical, \ diagonal, horizontal and / diagonal. See `is_win`.
+            let y = self.0 & (self.0 >> shift);
+            win |= y & (y >> (2 * shift));
+        }
+        win != 0
+    }
+
+    /// The same stones reflected at the middle column.
+    pub fn mirror(self) -> Self {
+        PlayerStones(mirror_columns::<W, H>(self.0))
+    }
+
     /// Changes the bitmask to represent the stones of the other player
-    pub fn flip(&mut self, mask: AllStones) {
+    pub fn flip(&mut self, mask: AllStones<W, H>) {
         self.0 ^= mask.0
     }
 
-    /// A unique key encoding the board. Starting from bit 49 everything is guaranteed to be zero.
-    /// Two different boards are guaranteed to have to different keys.
-    pub fn key(self, mask: AllStones) -> u64 {
+    /// Removes the bottom cell of the column and lets the cells above fall down by one row.
+    pub fn pop(&mut self, column: u8) {
+        self.0 = pop_column::<H>(self.0, column)
+    }
+
+    /// A unique key encoding the board. Starting from bit `W * (H + 1)` (49 for the standard board)
+    /// everything is guaranteed to be zero. Two different boards are guaranteed to have to
+    /// different keys.
+    pub fn key(self, mask: AllStones<W, H>) -> u64 {
         self.0 + mask.0
     }
 
@@ -92,25 +151,43 @@ impl PlayerStones {
         };
 
         // Horizontal; Can be won by adding a stone left, right, but also by filling a gap.
-        winning |= add_left_right_gaps(6 + 1);
+        winning |= add_left_right_gaps(H + 1);
 
         // Diagonal; Bottom left to top right
-        winning |= add_left_right_gaps(6 + 1 + 1);
+        winning |= add_left_right_gaps(H + 1 + 1);
 
         // Diagonal; Top left to bottom right
-        winning |= add_left_right_gaps(6 + 1 - 1);
+        winning |= add_left_right_gaps(H + 1 - 1);
 
-        winning & FULL
+        winning & full::<W, H>()
     }
 }
 
+/// Swaps the `H + 1` bits of the first column with the ones of the last, the second with the second
+/// to last and so on.
+pub fn mirror_columns<const W: usize, const H: usize>(bits: u64) -> u64 {
+    let column_bits = (1 << (H + 1)) - 1;
+    (0..W).fold(0, |mirrored, column| {
+        mirrored | ((bits >> ((H + 1) * column)) & column_bits) << ((H + 1) * (W - 1 - column))
+    })
+}
+
+/// Shifts the bits of one column down by one row. The bit of the bottom row is dropped, the other
+/// columns stay as they are.
+fn pop_column<const H: usize>(bits: u64, column: u8) -> u64 {
+    let mask = self::column::<H>(column);
+    // Shifting the bottom bit of the column moves it into the bit on top of the column to the left,
+    // which is outside of the mask.
+    (bits & !mask) | ((bits & mask) >> 1 & mask)
+}
+
 /// Return a bitmask, with 0 everywhere but the Bit identifed by row and column
-const fn cell(row: u8, column: u8) -> u64 {
-    1u64 << (7 * column + row)
+pub const fn cell<const H: usize>(row: u8, column: u8) -> u64 {
+    1u64 << ((H + 1) * column as usize + row as usize)
 }
 
-/// Bitboard containing stones of both players. First seven bits represent first column, second
-/// seven bits the second column and so on.
+/// Bitboard containing stones of both players. First `H + 1` bits represent first column, second
+/// `H + 1` bits the second column and so on. For the standard board:
 ///
 /// .  .  .  .  .  .  .  TOP
 /// 5 12 19 26 33 40 47
@@ -121,21 +198,26 @@ const fn cell(row: u8, column: u8) -> u64 {
 /// 0  7 14 21 28 35 42  BOTTOM
 /// The bits 6, 13, 20, 27, 34, 41, >= 48 have to be 0
 #[derive(Clone, Copy, PartialEq, Eq, Default, Hash)]
-pub struct AllStones(u64);
+pub struct AllStones<const W: usize = 7, const H: usize = 6>(u64);
 
-impl AllStones {
+impl<const W: usize, const H: usize> AllStones<W, H> {
     /// Mask with one stone at the bottom of each column.
-    #[allow(clippy::unusual_byte_groupings)] // Group by column rather than byte ;-)
-    const BOTTOM: u64 = 0b0000001_0000001_0000001_0000001_0000001_0000001_0000001u64;
+    const BOTTOM: u64 = bottom::<W, H>();
 
-    /// `true` if the column indentified by the index contains six stones.
+    /// `true` if the column indentified by the index contains `H` stones.
     pub fn is_full(self, column: u8) -> bool {
-        !self.is_empty(5, column)
+        !self.is_empty(H as u8 - 1, column)
     }
 
     /// Add a stone into a column. User must check before if column is already full.
     pub fn insert(&mut self, column: u8) {
-        self.0 |= self.0 + cell(0, column);
+        self.0 |= self.0 + cell::<H>(0, column);
+    }
+
+    /// Removes the bottom stone of a column. The stones above fall down by one row. User must check
+    /// before that the column is not empty.
+    pub fn pop(&mut self, column: u8) {
+        self.0 = pop_column::<H>(self.0, column)
     }
 
     /// Total number of stones in the board
@@ -143,26 +225,42 @@ impl AllStones {
         self.0.count_ones() as u8
     }
 
+    /// Number of stones in the column
+    pub fn height(self, column: u8) -> u8 {
+        (self.0 & self::column::<H>(column)).count_ones() as u8
+    }
+
     /// Tells if the board has a stone in the specified place. The bottom row and the leftmost
     /// column are `0`.
     pub fn is_empty(self, row: u8, column: u8) -> bool {
-        (cell(row, column) & self.0) == 0
+        (cell::<H>(row, column) & self.0) == 0
+    }
+
+    /// The same stones reflected at the middle column.
+    pub fn mirror(self) -> Self {
+        AllStones(mirror_columns::<W, H>(self.0))
+    }
+
+    /// Bitmask of the cells without a stone
+    pub fn empty(self) -> u64 {
+        full::<W, H>() & !self.0
     }
 
     /// Bitmask with ossible positions for the next stone to land in
     pub fn possible(self) -> u64 {
-        (self.0 + Self::BOTTOM) & FULL
+        (self.0 + Self::BOTTOM) & full::<W, H>()
     }
 }
 
 /// Bitmask representing all moves which do not give the opponent an immediate chance to win.
 #[derive(Clone, Copy)]
-pub struct NonLoosingMoves(u64);
+pub struct NonLoosingMoves<const H: usize = 6>(u64);
 
-impl NonLoosingMoves {
-    pub fn new(opponent: PlayerStones, both: AllStones) -> Self {
+impl<const H: usize> NonLoosingMoves<H> {
+    /// `openings` are the winning positions of the opponent, as returned by
+    /// [`PlayerStones::winning_positions`].
+    pub fn new<const W: usize>(openings: u64, both: AllStones<W, H>) -> Self {
         // Check if we need to block a stone, to prevent the opponent from winning
-        let openings = opponent.winning_positions();
         let mut possible = both.possible();
         let forced_moves = openings & possible;
         if forced_moves != 0 {
@@ -183,25 +281,46 @@ impl NonLoosingMoves {
 
     /// `true` if throwing a stone in the indexed column is not loosing immediatly.
     pub fn contains(self, index: u8) -> bool {
-        self.0 & column(index) != 0
+        self.0 & column::<H>(index) != 0
     }
 }
 
-pub fn heuristic(player: PlayerStones, both: AllStones) -> u32 {
-    let openings = player.winning_positions();
+/// `openings` are the winning positions of a player, as returned by
+/// [`PlayerStones::winning_positions`].
+pub fn heuristic<const W: usize, const H: usize>(openings: u64, both: AllStones<W, H>) -> u32 {
     // only count openings, which are not blocked by enemy stones already
     let true_openings = openings & !(both.0);
     true_openings.count_ones()
 }
 
 /// Mask a column with all `1`s
-fn column(index: u8) -> u64 {
-    0b111111 << (index * (6 + 1))
+pub fn column<const H: usize>(index: u8) -> u64 {
+    ((1 << H) - 1) << (index as usize * (H + 1))
+}
+
+/// Mask with all `1`s in every second row, starting with `first_row`.
+pub fn every_other_row<const W: usize, const H: usize>(first_row: usize) -> u64 {
+    (first_row..H)
+        .step_by(2)
+        .fold(0, |mask, row| mask | bottom::<W, H>() << row)
 }
 
-/// Mask with one stone in each column of the board
-#[allow(clippy::unusual_byte_groupings)] // Group by column rather than byte ;-)
-const FULL: u64 = 0b0111111_0111111_0111111_0111111_0111111_0111111_0111111u64;
+/// Mask with one stone at the bottom of each column.
+const fn bottom<const W: usize, const H: usize>() -> u64 {
+    let mut mask = 0;
+    let mut column = 0;
+    while column < W {
+        mask |= 1 << (column * (H + 1));
+        column += 1;
+    }
+    mask
+}
+
+/// Mask with all cells of the board set. E.g. for the standard board:
+/// `0b0111111_0111111_0111111_0111111_0111111_0111111_0111111`
+const fn full<const W: usize, const H: usize>() -> u64 {
+    bottom::<W, H>() * ((1 << H) - 1)
+}
 
 #[cfg(test)]
 mod test {
@@ -210,7 +329,7 @@ mod test {
 
     #[test]
     fn place_stones() {
-        let mut board = PlayerStones::new();
+        let mut board: PlayerStones = PlayerStones::new();
         assert!(board.is_empty(0, 2));
         board.place_stone(0, 2);
         assert!(!board.is_empty(0, 2));
@@ -222,7 +341,7 @@ mod test {
 
     #[test]
     fn horizontal() {
-        let mut board = PlayerStones::new();
+        let mut board: PlayerStones = PlayerStones::new();
         board.place_stone(0, 1);
         assert!(!board.is_win());
         board.place_stone(0, 2);
@@ -235,7 +354,7 @@ mod test {
 
     #[test]
     fn vertical() {
-        let mut board = PlayerStones::new();
+        let mut board: PlayerStones = PlayerStones::new();
         board.place_stone(1, 2);
         assert!(!board.is_win());
         board.place_stone(2, 2);
@@ -248,7 +367,7 @@ mod test {
 
     #[test]
     fn diagonal1() {
-        let mut board = PlayerStones::new();
+        let mut board: PlayerStones = PlayerStones::new();
         board.place_stone(1, 1);
         assert!(!board.is_win());
         board.place_stone(2, 2);
@@ -261,7 +380,7 @@ mod test {
 
     #[test]
     fn diagonal2() {
-        let mut board = PlayerStones::new();
+        let mut board: PlayerStones = PlayerStones::new();
         board.place_stone(1, 4);
         assert!(!board.is_win());
         board.place_stone(2, 3);
@@ -271,4 +390,23 @@ mod test {
         board.place_stone(4, 1);
         assert!(board.is_win());
     }
+
+    #[test]
+    fn pop_shifts_column_down() {
+        let mut board: PlayerStones = PlayerStones::new();
+        board.place_stone(5, 1);
+        board.place_stone(0, 2);
+        board.place_stone(1, 2);
+        board.place_stone(3, 2);
+        board.place_stone(0, 3);
+
+        board.pop(2);
+
+        let mut expected: PlayerStones = PlayerStones::new();
+        expected.place_stone(5, 1);
+        expected.place_stone(0, 2);
+        expected.place_stone(2, 2);
+        expected.place_stone(0, 3);
+        assert!(expected == board);
+    }
 }
@@ -0,0 +1,113 @@
+use std::fmt;
+
+use crate::{
+    bitboard::{AllStones, PlayerStones},
+    Board, Player,
+};
+
+/// Builder for the standard [`crate::ConnectFour`] board.
+pub type ConnectFourBuilder = BoardBuilder<7, 6>;
+
+/// Sets up a board cell by cell, rather than move by move. Useful to construct positions for tests
+/// which would be tedious to reach with a move list, or can not be reached by legal play at all.
+///
+/// [`Self::build`] only checks that no stone is floating above an empty cell. It does not check
+/// whether both players have a fair share of stones, or whether somebody already won. As in a
+/// regular game, the player to move is derived from the number of stones: Player one moves next if
+/// it is even, player two otherwise. An odd number of stones with player two owning more of them
+/// than player one is the exception: That board is built with the players swapped (see
+/// [`Board::swap_players`]), so player one moves next and the stones keep their owners.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BoardBuilder<const W: usize, const H: usize> {
+    /// Indexed by `[row][column]` like [`Board::to_grid`].
+    grid: [[Option<Player>; W]; H],
+}
+
+impl<const W: usize, const H: usize> BoardBuilder<W, H> {
+    /// Builder for an empty board
+    pub fn new() -> Self {
+        Self {
+            grid: [[None; W]; H],
+        }
+    }
+
+    /// Places a stone of `player` in the cell at `row` and `column`, or empties it for `None`. The
+    /// bottom row and the leftmost column are `0`. Panics if the cell is not on the board.
+    pub fn cell(mut self, row: u8, column: u8, player: Option<Player>) -> Self {
+        assert!((row as usize) < H && (column as usize) < W);
+        self.grid[row as usize][column as usize] = player;
+        self
+    }
+
+    /// The board with the stones placed so far, unless one of them is floating.
+    pub fn build(&self) -> Result<Board<W, H>, BuildError> {
+        let mut board = Board::new();
+        let stones = self.grid.iter().flatten().flatten().count();
+        let stones_of_two = self
+            .grid
+            .iter()
+            .flatten()
+            .filter(|&&cell| cell == Some(Player::Two))
+            .count();
+        let swapped = stones % 2 == 1 && 2 * stones_of_two > stones;
+        if swapped {
+            board.swap_players();
+        }
+        let last_player = if (stones % 2 == 1) != swapped {
+            Player::One
+        } else {
+            Player::Two
+        };
+        let mut last = PlayerStones::new();
+        let mut both = AllStones::default();
+        for column in 0..W as u8 {
+            let mut below_is_empty = false;
+            for row in 0..H as u8 {
+                match self.grid[row as usize][column as usize] {
+                    None => below_is_empty = true,
+                    Some(_) if below_is_empty => {
+                        return Err(BuildError::FloatingStone { row, column });
+                    }
+                    Some(player) => {
+                        both.insert(column);
+                        if player == last_player {
+                            last.place_stone(row, column);
+                        }
+                    }
+                }
+            }
+        }
+        board.last = last;
+        board.both = both;
+        board.won = last.is_win();
+        Ok(board)
+    }
+}
+
+impl<const W: usize, const H: usize> Default for BoardBuilder<W, H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Reason why [`BoardBuilder::build`] could not construct a board.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BuildError {
+    /// The cell below the stone at `row` and `column` is empty.
+    FloatingStone { row: u8, column: u8 },
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BuildError::FloatingStone { row, column } => write!(
+                f,
+                "Stone in row {} of column {} is floating.",
+                row + 1,
+                column + 1
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
@@ -0,0 +1,31 @@
+use std::ops::{Index, IndexMut};
+
+/// One value for each cell of the standard board, e.g. scores, heat map values or highlight flags
+/// to show along with a board. Indexed by `(row, column)` like [`crate::ConnectFour::to_grid`]:
+/// Row `0` is the bottom row and column `0` the leftmost column. Print the values on top of a board
+/// with [`crate::ConnectFour::render_cell_map`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub struct CellMap<T>(pub [[T; 7]; 6]);
+
+impl<T> CellMap<T> {
+    /// Map holding `value(row, column)` for each cell.
+    pub fn from_fn(mut value: impl FnMut(u8, u8) -> T) -> Self {
+        CellMap(std::array::from_fn(|row| {
+            std::array::from_fn(|column| value(row as u8, column as u8))
+        }))
+    }
+}
+
+impl<T> Index<(u8, u8)> for CellMap<T> {
+    type Output = T;
+
+    fn index(&self, (row, column): (u8, u8)) -> &T {
+        &self.0[row as usize][column as usize]
+    }
+}
+
+impl<T> IndexMut<(u8, u8)> for CellMap<T> {
+    fn index_mut(&mut self, (row, column): (u8, u8)) -> &mut T {
+        &mut self.0[row as usize][column as usize]
+    }
+}
@@ -0,0 +1,67 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::Path,
+};
+
+use crate::{ConnectFour, NotationFormat, Solver};
+
+/// Reads the positions of the dataset at `path`, each as its move list, the position and its
+/// expected score.
+///
+/// Each line holds a move list, like [`ConnectFour::from_move_list`] expects it, and the expected
+/// score, separated by whitespace. This is the format of the test sets in the `tests` folder of
+/// this crate, e.g. `"2252576253462244111563365343671351441 -1"`. Empty lines are skipped. Lines
+/// are read one at a time, so large files are fine. Fails if the file can not be opened. Yields an
+/// error for each line which can not be read, or is not in the format described.
+pub fn read_dataset(
+    path: impl AsRef<Path>,
+) -> io::Result<impl Iterator<Item = io::Result<(String, ConnectFour, i8)>>> {
+    let input = BufReader::new(File::open(path)?);
+    let entries = input
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| line.and_then(|line| parse_line(index, &line)).transpose());
+    Ok(entries)
+}
+
+/// Move list, position and score of the line with the zero based `index`. `None` for empty lines.
+fn parse_line(index: usize, line: &str) -> io::Result<Option<(String, ConnectFour, i8)>> {
+    let mut tokens = line.split_whitespace();
+    let Some(moves) = tokens.next() else {
+        return Ok(None);
+    };
+    let invalid = || {
+        let message = format!("Line {} is not a move list and a score: {line}", index + 1);
+        io::Error::new(io::ErrorKind::InvalidData, message)
+    };
+    let game =
+        ConnectFour::from_notation(moves, NotationFormat::Packed1Indexed).map_err(|_| invalid())?;
+    let score: i8 = tokens
+        .next()
+        .and_then(|score| score.parse().ok())
+        .ok_or_else(invalid)?;
+    if tokens.next().is_some() {
+        return Err(invalid());
+    }
+    Ok(Some((moves.to_owned(), game, score)))
+}
+
+/// Scores each position of the dataset at `path` and returns the positions scored differently
+/// than expected, as the move list, the expected and the actual score. Empty if the solver agrees
+/// with every line. See [`read_dataset`] for the format. Fails if the file can not be read, or a
+/// line is not in that format.
+pub fn verify_dataset(
+    path: impl AsRef<Path>,
+    solver: &mut Solver,
+) -> io::Result<Vec<(String, i8, i8)>> {
+    let mut mismatches = Vec::new();
+    for entry in read_dataset(path)? {
+        let (moves, game, expected) = entry?;
+        let actual = solver.score(&game);
+        if actual != expected {
+            mismatches.push((moves, expected, actual));
+        }
+    }
+    Ok(mismatches)
+}
@@ -0,0 +1,82 @@
+use crate::{
+    rng::{choose, Rng},
+    Column, ConnectFour, Solver,
+};
+
+/// Playing strength of [`Solver::suggest_move`]. All levels but `Perfect` pick randomly among the
+/// moves they consider equally good, so they do not repeat the same game over and over.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Difficulty {
+    /// Looks two stones ahead: Takes a win with the next stone and blocks an immediate win of the
+    /// opponent, but falls for any threat further down the line. One in three moves is picked
+    /// completely at random, even if it loses on the spot.
+    Easy,
+    /// Looks six stones ahead. One in ten moves is picked completely at random.
+    Medium,
+    /// Looks twelve stones ahead and never plays a random move. Still misses wins and losses which
+    /// take longer than that, which is most of them early in the game.
+    Hard,
+    /// Plays perfectly, like the first of [`Solver::best_moves`]. Deterministic and may take
+    /// seconds for some positions early in the game.
+    Perfect,
+}
+
+impl Difficulty {
+    /// Number of stones the search looks ahead, counting the suggested one. `None` for perfect
+    /// play.
+    fn depth(self) -> Option<u8> {
+        match self {
+            Difficulty::Easy => Some(2),
+            Difficulty::Medium => Some(6),
+            Difficulty::Hard => Some(12),
+            Difficulty::Perfect => None,
+        }
+    }
+
+    /// A random move is played once in that many moves. `None` if never.
+    fn blunder_every(self) -> Option<u64> {
+        match self {
+            Difficulty::Easy => Some(3),
+            Difficulty::Medium => Some(10),
+            Difficulty::Hard | Difficulty::Perfect => None,
+        }
+    }
+}
+
+impl Solver {
+    /// A move for the current player, as strong as `difficulty` demands. `rng` decides ties and
+    /// deliberate blunders, so the same sequence of random numbers yields the same move. `None` if
+    /// the game is over.
+    pub fn suggest_move(
+        &mut self,
+        game: &ConnectFour,
+        difficulty: Difficulty,
+        rng: &mut impl Rng,
+    ) -> Option<Column> {
+        if game.is_over() {
+            return None;
+        }
+        let Some(depth) = difficulty.depth() else {
+            let mut best_moves = Vec::new();
+            self.best_moves(game, &mut best_moves);
+            return best_moves.first().copied();
+        };
+        if let Some(every) = difficulty.blunder_every() {
+            if rng.next_u64().is_multiple_of(every) {
+                let legal_moves: Vec<Column> = game.legal_moves().collect();
+                return choose(rng, &legal_moves);
+            }
+        }
+        let scores: Vec<(Column, i8)> = game
+            .children()
+            .map(|(column, child)| (column, -self.score_limited(&child, depth - 1)))
+            .collect();
+        let best = scores.iter().map(|&(_, score)| score).max()?;
+        let best_moves: Vec<Column> = scores
+            .iter()
+            .filter(|&&(_, score)| score == best)
+            .map(|&(column, _)| column)
+            .collect();
+        choose(rng, &best_moves)
+    }
+}
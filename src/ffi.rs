@@ -0,0 +1,235 @@
+//! C compatible interface for using the solver from other languages. Only available with the `ffi`
+//! feature.
+//!
+//! Boards and solvers are handed out as opaque pointers, which must be released with
+//! [`cf_board_free`] and [`cf_solver_free`] respectively. Invalid arguments, like null pointers or
+//! columns outside the board, are reported as errors rather than causing a panic. Should a panic
+//! happen nonetheless, it is caught at the boundary and reported like an error: Functions returning
+//! pointers return null, functions returning `bool` return `false`, [`cf_encode`] and [`cf_score`]
+//! return [`CF_ENCODE_ERROR`] and [`CF_SCORE_ERROR`]. Catching panics requires the library to be
+//! built with `panic = "unwind"`, which is not the case for the release profile of this crate.
+
+use std::{
+    ffi::{c_char, CStr},
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr,
+};
+
+use crate::{Column, ConnectFour, Solver};
+
+/// Returned by [`cf_score`] in case of an error, including a panic.
+pub const CF_SCORE_ERROR: i8 = i8::MIN;
+
+/// Returned by [`cf_encode`] in case of an error, including a panic. Valid keys never use more than 49 bits.
+pub const CF_ENCODE_ERROR: u64 = u64::MAX;
+
+/// Creates an empty board. Release it with [`cf_board_free`].
+#[no_mangle]
+pub extern "C" fn cf_board_new() -> *mut ConnectFour {
+    catch_panic(ptr::null_mut(), || {
+        Box::into_raw(Box::new(ConnectFour::new()))
+    })
+}
+
+/// Creates a board from a zero terminated sequence of moves, e.g. `"4453"`. Each move is a digit
+/// from 1 to 7. Returns null if the move list contains invalid or illegal moves, including moves
+/// after the game is over. Release the board with [`cf_board_free`].
+///
+/// # Safety
+///
+/// `move_list` must be null or point to a zero terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn cf_board_from_move_list(move_list: *const c_char) -> *mut ConnectFour {
+    if move_list.is_null() {
+        return ptr::null_mut();
+    }
+    catch_panic(ptr::null_mut(), || {
+        let move_list = CStr::from_ptr(move_list).to_bytes();
+        let mut game = ConnectFour::new();
+        for &c in move_list {
+            match (c as char).to_string().parse() {
+                Ok(column) if !game.is_over() && game.play(column) => (),
+                _ => return ptr::null_mut(),
+            }
+        }
+        Box::into_raw(Box::new(game))
+    })
+}
+
+/// Releases a board created by this library. Does nothing for null.
+///
+/// # Safety
+///
+/// `board` must be null or a pointer obtained from this library, which has not been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn cf_board_free(board: *mut ConnectFour) {
+    if !board.is_null() {
+        catch_panic((), || drop(Box::from_raw(board)));
+    }
+}
+
+/// Inserts a stone for the current player into the column with the index `column` (`0` to `6`).
+/// `true` if the move has been legal. `false` for illegal moves, if the game is already over, or if
+/// `board` is null.
+///
+/// # Safety
+///
+/// `board` must be null or a valid pointer obtained from [`cf_board_new`] or
+/// [`cf_board_from_move_list`].
+#[no_mangle]
+pub unsafe extern "C" fn cf_play(board: *mut ConnectFour, column: u8) -> bool {
+    let Some(board) = board.as_mut() else {
+        return false;
+    };
+    catch_panic(false, || {
+        column < 7 && !board.is_over() && board.play(Column::from_index(column))
+    })
+}
+
+/// `true` if the game has a winner or is a draw. `false` if `board` is null.
+///
+/// # Safety
+///
+/// `board` must be null or a valid pointer obtained from [`cf_board_new`] or
+/// [`cf_board_from_move_list`].
+#[no_mangle]
+pub unsafe extern "C" fn cf_is_over(board: *const ConnectFour) -> bool {
+    catch_panic(false, || board.as_ref().is_some_and(ConnectFour::is_over))
+}
+
+/// Unique key of the board. See [`ConnectFour::encode`]. Returns [`CF_ENCODE_ERROR`] if `board` is
+/// null.
+///
+/// # Safety
+///
+/// `board` must be null or a valid pointer obtained from [`cf_board_new`] or
+/// [`cf_board_from_move_list`].
+#[no_mangle]
+pub unsafe extern "C" fn cf_encode(board: *const ConnectFour) -> u64 {
+    catch_panic(CF_ENCODE_ERROR, || {
+        board
+            .as_ref()
+            .map(ConnectFour::encode)
+            .unwrap_or(CF_ENCODE_ERROR)
+    })
+}
+
+/// Creates a solver with the default transposition table of about 64MiB. Release it with
+/// [`cf_solver_free`].
+#[no_mangle]
+pub extern "C" fn cf_solver_new() -> *mut Solver {
+    catch_panic(ptr::null_mut(), || Box::into_raw(Box::new(Solver::new())))
+}
+
+/// Releases a solver created by this library. Does nothing for null.
+///
+/// # Safety
+///
+/// `solver` must be null or a pointer obtained from [`cf_solver_new`], which has not been released
+/// yet.
+#[no_mangle]
+pub unsafe extern "C" fn cf_solver_free(solver: *mut Solver) {
+    if !solver.is_null() {
+        catch_panic((), || drop(Box::from_raw(solver)));
+    }
+}
+
+/// Score of the board. See [`Solver::score`]. Returns [`CF_SCORE_ERROR`] if any of the pointers is
+/// null, or the calculation failed.
+///
+/// # Safety
+///
+/// `solver` must be null or a valid pointer obtained from [`cf_solver_new`]. `board` must be null
+/// or a valid pointer obtained from [`cf_board_new`] or [`cf_board_from_move_list`].
+#[no_mangle]
+pub unsafe extern "C" fn cf_score(solver: *mut Solver, board: *const ConnectFour) -> i8 {
+    let (Some(solver), Some(board)) = (solver.as_mut(), board.as_ref()) else {
+        return CF_SCORE_ERROR;
+    };
+    catch_panic(CF_SCORE_ERROR, || solver.score(board))
+}
+
+/// Result of `f`, or `error` if `f` panics. Keeps panics from unwinding into the caller of the
+/// library, which is undefined behaviour.
+fn catch_panic<T>(error: T, f: impl FnOnce() -> T) -> T {
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or(error)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr;
+
+    use super::*;
+
+    #[test]
+    fn play_and_score() {
+        unsafe {
+            let board = cf_board_from_move_list(c"2252576253462244111563365343671351441".as_ptr());
+            let solver = cf_solver_new();
+
+            assert_eq!(-1, cf_score(solver, board));
+            assert!(!cf_play(board, 0));
+            assert!(cf_play(board, 6));
+            assert!(!cf_is_over(board));
+
+            cf_solver_free(solver);
+            cf_board_free(board);
+        }
+    }
+
+    #[test]
+    fn no_moves_after_game_is_over() {
+        unsafe {
+            let board = cf_board_from_move_list(c"1212121".as_ptr());
+
+            assert!(cf_is_over(board));
+            assert!(!cf_play(board, 2));
+
+            cf_board_free(board);
+        }
+    }
+
+    #[test]
+    fn invalid_move_list() {
+        unsafe {
+            assert!(cf_board_from_move_list(c"1118".as_ptr()).is_null());
+            // Column is full after six stones
+            assert!(cf_board_from_move_list(c"1111111".as_ptr()).is_null());
+            // Player one has won after the seventh stone
+            assert!(cf_board_from_move_list(c"12121213".as_ptr()).is_null());
+            assert!(cf_board_from_move_list(ptr::null()).is_null());
+        }
+    }
+
+    #[test]
+    fn null_pointers_are_errors() {
+        unsafe {
+            assert!(!cf_play(ptr::null_mut(), 3));
+            assert_eq!(CF_ENCODE_ERROR, cf_encode(ptr::null()));
+            assert_eq!(CF_SCORE_ERROR, cf_score(ptr::null_mut(), ptr::null()));
+            cf_board_free(ptr::null_mut());
+            cf_solver_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn panics_are_errors() {
+        let score = catch_panic(CF_SCORE_ERROR, || panic!("Solver is broken"));
+        let board: *mut ConnectFour = catch_panic(ptr::null_mut(), || panic!("Board is broken"));
+
+        assert_eq!(CF_SCORE_ERROR, score);
+        assert!(board.is_null());
+    }
+
+    #[test]
+    fn encode_matches_board() {
+        unsafe {
+            let board = cf_board_new();
+            assert!(cf_play(board, 3));
+
+            assert_eq!(ConnectFour::from_move_list("4").encode(), cf_encode(board));
+
+            cf_board_free(board);
+        }
+    }
+}
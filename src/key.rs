@@ -0,0 +1,114 @@
+use std::fmt;
+
+use crate::{bitboard::cell, ConnectFour, ConnectFourBuilder, Player};
+
+/// Reason why [`ConnectFour::from_key`] or [`ConnectFour::from_grid`] could not reconstruct a board.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DecodeError {
+    /// The key is not of the form [`ConnectFour::encode`] produces, e.g. it has bits set beyond the
+    /// board, or a column with more than six stones. For [`ConnectFour::from_grid`]: A stone is
+    /// floating above an empty cell.
+    InvalidLayout,
+    /// The key describes a board which can not be reached by legal play, e.g. because one player
+    /// has put in too many stones, both of them have won, or the game has been won before the last
+    /// stone.
+    Unreachable,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::InvalidLayout => write!(f, "Key or grid does not describe a board."),
+            DecodeError::Unreachable => write!(f, "Board can not be reached by legal play."),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl ConnectFour {
+    /// Inverse of [`ConnectFour::encode`]. Rejects keys which are not laid out like the ones
+    /// `encode` produces, and boards which can not be reached by legal play: The player who put in
+    /// the last stone must have put in as many stones as the other one, or one more. Only they may
+    /// have four in a row, and only if taking back one of their topmost stones undoes it. The
+    /// players are not swapped (see [`ConnectFour::swap_players`]), since the key does not tell.
+    pub fn from_key(key: u64) -> Result<ConnectFour, DecodeError> {
+        if key >> 49 != 0 {
+            return Err(DecodeError::InvalidLayout);
+        }
+        // Height of each column, and the stones of the player who put in the last stone.
+        let columns: [(u32, u64); 7] = std::array::from_fn(|column| {
+            // Stones of the column (all ones from the bottom) plus the ones of the player who put in
+            // the last stone. See `PlayerStones::key`.
+            let bits = (key >> (7 * column)) & 0b111_1111;
+            let height = if (bits + 1).is_power_of_two() {
+                bits.count_ones()
+            } else {
+                u64::BITS - 1 - bits.leading_zeros()
+            };
+            (height, bits - ((1 << height) - 1))
+        });
+        if columns.iter().any(|&(height, _)| height > 6) {
+            return Err(DecodeError::InvalidLayout);
+        }
+        let stones: u32 = columns.iter().map(|&(height, _)| height).sum();
+        let last_stones: u32 = columns.iter().map(|&(_, last)| last.count_ones()).sum();
+        // Fill the bitboards directly, rather than with the builder. It would swap the players if
+        // the other one owns more stones, and we want to report that as unreachable.
+        let mut game = ConnectFour::new();
+        for (column, &(height, last)) in (0..).zip(&columns) {
+            for row in 0..height as u8 {
+                game.both.insert(column);
+                if last >> row & 1 == 1 {
+                    game.last.place_stone(row, column);
+                }
+            }
+        }
+        if game.encode() != key {
+            return Err(DecodeError::InvalidLayout);
+        }
+        game.won = game.last.is_win();
+        // The player who put in the last stone has put in as many stones as the other one, or one
+        // more. The other one can not have won already.
+        let mut current = game.last;
+        current.flip(game.both);
+        let fair_share = last_stones == stones.div_ceil(2);
+        if !fair_share || current.is_win() {
+            return Err(DecodeError::Unreachable);
+        }
+        // The game ends with the first four in a row, so the last stone must have completed every
+        // one of them.
+        let won_with_last_stone = (0..7).any(|column| {
+            let height = game.both.height(column);
+            height > 0
+                && !game.last.is_empty(height - 1, column)
+                && !game
+                    .last
+                    .without_stones(cell::<6>(height - 1, column))
+                    .is_win()
+        });
+        if game.last.is_win() && !won_with_last_stone {
+            return Err(DecodeError::Unreachable);
+        }
+        Ok(game)
+    }
+
+    /// Board holding the stones of `grid`, indexed like [`ConnectFour::to_grid`]. Unlike
+    /// [`ConnectFourBuilder`], which accepts any grid without floating stones, this only accepts
+    /// boards which can be reached by legal play, like [`Self::from_key`]. Meant for grids from
+    /// untrusted sources, e.g. a board recognized in an image. Like the builder, this swaps the
+    /// players if player two owns more stones, so every stone keeps its owner.
+    pub fn from_grid(grid: &[[Option<Player>; 7]; 6]) -> Result<ConnectFour, DecodeError> {
+        let mut builder = ConnectFourBuilder::new();
+        for (row, cells) in (0..).zip(grid) {
+            for (column, &player) in (0..).zip(cells) {
+                builder = builder.cell(row, column, player);
+            }
+        }
+        let board = builder.build().map_err(|_| DecodeError::InvalidLayout)?;
+        // The key tells who put in the last stone, but not whether the players have been swapped.
+        // So only check the key, and keep the board of the builder.
+        ConnectFour::from_key(board.encode())?;
+        Ok(board)
+    }
+}
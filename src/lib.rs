@@ -1,22 +1,65 @@
 mod bitboard;
+mod builder;
+mod cell_map;
+mod dataset;
+mod difficulty;
+mod evaluation;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod key;
+mod notation;
+mod phase;
+mod positions;
 mod precalculated;
+#[cfg(feature = "protocol")]
+pub mod protocol;
+mod render;
+mod rng;
 mod solver;
 mod transposition_table;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+mod zobrist;
 
 use self::bitboard::PlayerStones;
-use std::{fmt, io, str::FromStr};
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    io,
+    str::FromStr,
+};
 
 use bitboard::{heuristic, AllStones, NonLoosingMoves};
-pub use solver::{score, Solver};
+pub use builder::{BoardBuilder, BuildError, ConnectFourBuilder};
+pub use cell_map::CellMap;
+pub use dataset::{read_dataset, verify_dataset};
+pub use difficulty::Difficulty;
+pub use key::DecodeError;
+pub use notation::{NotationError, NotationFormat, PlayedGame};
+pub use phase::Phase;
+pub use positions::{count_positions, unique_positions, PositionSet, TreeVisitor, Visit};
+pub use precalculated::precalculated_depth;
+pub use render::RenderStyle;
+pub use rng::{Rng, XorShift};
+pub use solver::{
+    interpret_score, score, GameStatus, Outcome, ScoreSource, Solution, Solver, SolverBuilder,
+    TieBreak,
+};
+#[cfg(feature = "rayon")]
+pub use transposition_table::ConcurrentTranspositionTable;
+
+/// Number of cells of the standard board, i.e. the number of stones it takes to fill it up.
+pub const TOTAL_CELLS: u8 = 42;
 
 /// An integer ranging from 0 to 6 representing a column of the connect four board.
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct Column(u8);
 
 impl Column {
-    /// Column index ranges from 0 to 6
+    /// Column index ranges from 0 to 6 for the standard board. Wider [`Board`]s allow for indices up
+    /// to their width minus one. No board is wider than 32 columns.
     pub const fn from_index(index: u8) -> Column {
-        assert!(index < 7);
+        assert!(index < 32);
         Column(index)
     }
 }
@@ -37,6 +80,46 @@ impl fmt::Display for Column {
     }
 }
 
+/// One of the two players of a connect four game. Player one puts in the first stone.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Player {
+    One,
+    Two,
+}
+
+/// Outcome of a finished game.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameResult {
+    /// The player completed a four in a row.
+    Victory(Player),
+    /// The board is full and nobody has won.
+    Draw,
+}
+
+/// Reason why a stone could not be put into the board. See [`ConnectFour::try_play`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PlayError {
+    /// The column already holds six stones (or as many as the board is high).
+    ColumnFull,
+    /// The column index is beyond the width of the board.
+    NoSuchColumn,
+    /// One of the players has already won, or the board is full.
+    GameAlreadyOver,
+}
+
+impl fmt::Display for PlayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            PlayError::ColumnFull => "Column is full.",
+            PlayError::NoSuchColumn => "No such column.",
+            PlayError::GameAlreadyOver => "Game is already over.",
+        };
+        write!(f, "{message}")
+    }
+}
+
+impl std::error::Error for PlayError {}
+
 /// State of a field in a four in a row board
 #[derive(Clone, Copy, PartialEq, Eq)]
 enum Cell {
@@ -48,46 +131,160 @@ enum Cell {
     PlayerTwo,
 }
 
-#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
-pub struct ConnectFour {
+/// The standard connect four board with seven columns and six rows. The solver and the
+/// precalculated scores only support this size.
+pub type ConnectFour = Board<7, 6>;
+
+/// A four in a row board `W` columns wide and `H` rows high. Use [`ConnectFour`] for the standard
+/// board. Other sizes are useful for experimenting with variants. All of the stones must fit into a
+/// 64 Bit bitboard with an extra bit on top of each column, i.e. `W * (H + 1) <= 64`. Looking for
+/// four in a row shifts the bitboard by up to three diagonal steps of `H + 2` bits, so `H` must not
+/// exceed `19`. Other sizes are rejected at compile time:
+///
+/// ```compile_fail
+/// use connect_four_solver::Board;
+///
+/// let board = Board::<3, 20>::new();
+/// ```
+///
+/// Two boards are equal if they hold the same stones and the same player moves next, just like
+/// their [encodings](Self::encode). Whether the players have been swapped (see
+/// [`Self::swap_players`]) does not matter, since it only changes the names of the players.
+#[derive(Clone, Copy, Default)]
+pub struct Board<const W: usize, const H: usize> {
     /// Bitborad encoding the stones of the player who did insert the last stone. Starts with Player
     /// two.
-    last: PlayerStones,
+    last: PlayerStones<W, H>,
     /// Bitboard encoding all cells containing stones, no matter the player.
-    both: AllStones,
+    both: AllStones<W, H>,
+    /// `true` if the players exchanged their colors, see [`Self::swap_players`]. Only affects which
+    /// stones are attributed to player one and two, not the bitboards.
+    players_swapped: bool,
+    /// `true` if the player who put in the last stone has four in a row. Updated whenever the
+    /// stones change, so [`Self::is_victory`] does not need to look at the bitboard.
+    won: bool,
 }
 
-impl ConnectFour {
+impl<const W: usize, const H: usize> Board<W, H> {
+    /// Checked at compile time for each board size in use.
+    const FITS_INTO_BITBOARD: () =
+        assert!(W * (H + 1) <= 64 && W <= 32 && H > 0 && 3 * (H + 2) < 64);
+
     /// Create an empty board
-    pub fn new() -> ConnectFour {
-        ConnectFour {
+    pub fn new() -> Self {
+        // Referring to the constant forces the compiler to evaluate the assertion.
+        let () = Self::FITS_INTO_BITBOARD;
+        Board {
             last: PlayerStones::new(),
             both: AllStones::default(),
+            players_swapped: false,
+            won: false,
         }
     }
 
+    /// Removes all stones from the board, so it is in the same state as a board created with
+    /// [`Self::new`].
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
     /// Inserts a stone for the current player. `true` if move has been legal
     pub fn play(&mut self, column: Column) -> bool {
         // Let's check if the move is legal, otherwise return false.
-        if self.both.is_full(column.0) {
+        if !self.is_legal_move(column) {
             return false;
         }
         // Now we add a stone to the bitmask for both player.
         self.both.insert(column.0);
         // Flip players after adding the stone, so the stone is accounted for the last player
         self.last.flip(self.both);
+        // Checks the whole board, not only the lines through the new stone. `is_win` takes a few
+        // shifts per direction, which is faster than finding the new stone and walking its lines.
+        self.won = self.last.is_win();
+        true
+    }
+
+    /// Like [`Self::play`], for moves which are known not to complete four in a row, e.g. because
+    /// [`Self::can_win_in_next_move`] is `false`. Skips the check for a win, which the search would
+    /// otherwise pay for at every node.
+    pub(crate) fn play_non_winning(&mut self, column: Column) -> bool {
+        if !self.is_legal_move(column) {
+            return false;
+        }
+        self.both.insert(column.0);
+        self.last.flip(self.both);
+        debug_assert!(!self.last.is_win());
+        self.won = false;
+        true
+    }
+
+    /// Like [`Self::play`], but tells why a move is illegal. Other than `play`, this also rejects
+    /// moves after the game is over.
+    pub fn try_play(&mut self, column: Column) -> Result<(), PlayError> {
+        if self.is_over() {
+            return Err(PlayError::GameAlreadyOver);
+        }
+        if column.0 as usize >= W {
+            return Err(PlayError::NoSuchColumn);
+        }
+        if !self.play(column) {
+            return Err(PlayError::ColumnFull);
+        }
+        Ok(())
+    }
+
+    /// Plays `moves` in order, like calling [`Self::try_play`] for each of them. Stops at the first
+    /// illegal move and returns its index within `moves`, together with the reason. The moves before
+    /// it remain applied, i.e. the board is left in the position right before the illegal move.
+    pub fn play_all(&mut self, moves: &[Column]) -> Result<(), (usize, PlayError)> {
+        for (index, &column) in moves.iter().enumerate() {
+            self.try_play(column).map_err(|error| (index, error))?;
+        }
+        Ok(())
+    }
+
+    /// Removes the bottom stone of `column` for the "Pop Out" variant. The stones above fall down by
+    /// one row and it is the turn of the other player afterwards. Legal only if the game is not over
+    /// yet and the bottom stone belongs to the current player. `true` if the move has been legal.
+    ///
+    /// Standard games never call this, and the solver does not know about it. Removing a stone may
+    /// complete lines for both players at once, yet [`Self::is_victory`] only looks at the lines of
+    /// the player who popped. Use [`Self::all_winning_lines`] to apply the rules of the variant.
+    pub fn pop(&mut self, column: Column) -> bool {
+        let mut current = self.last;
+        current.flip(self.both);
+        if self.is_over() || column.0 as usize >= W || current.is_empty(0, column.0) {
+            return false;
+        }
+        current.pop(column.0);
+        self.both.pop(column.0);
+        // The player who popped is the one who acted last now.
+        self.last = current;
+        self.won = self.last.is_win();
         true
     }
 
-    /// `true` if the column is not full.
+    /// Exchanges the roles of the players, as the pie rule of some variants allows the second player
+    /// to do after the first stone. The stones placed so far change their owner, and the player who
+    /// moves next is the opponent of whoever now owns the last stone. E.g. after the first stone it
+    /// belongs to player two, and player one moves next.
+    ///
+    /// The position itself does not change, only the names of the players, so the score stays the
+    /// same and the solver is unaffected. Standard games never call this.
+    pub fn swap_players(&mut self) {
+        self.players_swapped = !self.players_swapped;
+    }
+
+    /// `true` if the column exists and is not full.
     pub fn is_legal_move(&self, column: Column) -> bool {
-        !self.both.is_full(column.0)
+        (column.0 as usize) < W && !self.both.is_full(column.0)
     }
 
     /// Create a game state from a sequence of moves. Each move represented as a number from 1 to 7
-    /// standing for the column the player put in their stones.
-    pub fn from_move_list(move_list: &str) -> ConnectFour {
-        let mut game = ConnectFour::new();
+    /// (or the width of the board) standing for the column the player put in their stones. Panics on
+    /// illegal moves. See [`Self::from_notation`] for other formats and a fallible alternative.
+    pub fn from_move_list(move_list: &str) -> Self {
+        let mut game = Self::new();
         for c in move_list
             .as_bytes()
             .iter()
@@ -102,29 +299,44 @@ impl ConnectFour {
     }
 
     /// Prints out a text representation of a board to `out`
-    pub fn print_to(&self, mut out: impl io::Write) -> io::Result<()> {
-        for row in (0..6).rev() {
-            for field in (0..7).map(|column| self.cell(row, column)) {
-                let c = match field {
-                    Cell::PlayerOne => 'X',
-                    Cell::PlayerTwo => 'O',
-                    Cell::Empty => ' ',
-                };
-                write!(out, "|{}", c)?;
-            }
-            writeln!(out, "|")?;
-        }
-        writeln!(out, "---------------\n 1 2 3 4 5 6 7")
+    pub fn print_to(&self, out: impl io::Write) -> io::Result<()> {
+        self.render(RenderStyle::Ascii, out)
     }
 
-    pub fn legal_moves(&self) -> impl Iterator<Item = Column> + use<'_>{
-        (0..7).map(Column::from_index).filter(move |&c| self.is_legal_move(c))
+    pub fn legal_moves(&self) -> impl Iterator<Item = Column> + use<'_, W, H> {
+        (0..W as u8).map(Column::from_index).filter(move |&c| self.is_legal_move(c))
+    }
+
+    /// Each of the [`Self::legal_moves`] together with the board after playing it.
+    pub fn children(&self) -> impl Iterator<Item = (Column, Self)> + use<'_, W, H> {
+        self.legal_moves().map(move |column| {
+            let mut child = *self;
+            child.play(column);
+            (column, child)
+        })
+    }
+
+    /// The column to play on `other` in order to reach this board, if it is exactly one legal move
+    /// ahead. `None` for any other board, e.g. if more or fewer stones have been played, or if
+    /// `other` is already over.
+    pub fn is_successor_of(&self, other: &Self) -> Option<Column> {
+        if other.is_over() {
+            return None;
+        }
+        other
+            .children()
+            .find(|(_, child)| child == self)
+            .map(|(column, _)| column)
     }
 
     /// Access any cell of the board and find out whether it is empty, or holding a stone of Player
     /// One or Two.
     fn cell(&self, row: u8, column: u8) -> Cell {
-        let players = [Cell::PlayerOne, Cell::PlayerTwo];
+        let players = if self.players_swapped {
+            [Cell::PlayerTwo, Cell::PlayerOne]
+        } else {
+            [Cell::PlayerOne, Cell::PlayerTwo]
+        };
         if self.both.is_empty(row, column) {
             Cell::Empty
         } else if self.last.is_empty(row, column) {
@@ -134,10 +346,71 @@ impl ConnectFour {
         }
     }
 
+    /// The board as a dense grid, indexed by `[row][column]`. Row `0` is the bottom row, i.e. the
+    /// one the first stone of each column falls into, and row `5` (`H - 1`) the top row. Column `0`
+    /// is the leftmost column, which is labeled `1` when printed. `None` marks an empty cell.
+    pub fn to_grid(&self) -> [[Option<Player>; W]; H] {
+        let mut grid = [[None; W]; H];
+        for (row, cells) in (0..).zip(&mut grid) {
+            for (column, cell) in (0..).zip(cells) {
+                *cell = match self.cell(row, column) {
+                    Cell::Empty => None,
+                    Cell::PlayerOne => Some(Player::One),
+                    Cell::PlayerTwo => Some(Player::Two),
+                };
+            }
+        }
+        grid
+    }
+
+    /// The board as two planes of `0.` and `1.`, indexed by `[plane][row][column]`, e.g. as input
+    /// for a convolutional network. Rows and columns are ordered like in [`Self::to_grid`], i.e. row
+    /// `0` is the bottom row.
+    ///
+    /// The planes are relative to the side to move, like [`crate::score`]: Plane `0` holds the stones
+    /// of the player who puts in the next stone, plane `1` those of their opponent (who put in the
+    /// last stone). So the same arrangement yields the same planes, no matter whether it is
+    /// player one or two to move.
+    pub fn to_planes(&self) -> [[[f32; W]; H]; 2] {
+        let mut current = self.last;
+        current.flip(self.both);
+        let mut planes = [[[0.; W]; H]; 2];
+        for (stones, plane) in [current, self.last].into_iter().zip(&mut planes) {
+            for (row, cells) in (0..).zip(plane) {
+                for (column, cell) in (0..).zip(cells) {
+                    if !stones.is_empty(row, column) {
+                        *cell = 1.;
+                    }
+                }
+            }
+        }
+        planes
+    }
+
     /// Heurisitc used to decide which moves to explore first, in order to allow for better pruning
     /// of the search tree. Higher means better for the player which put in the last stone.
-    fn heuristic(&self) -> u32 {
-        heuristic(self.last, self.both)
+    ///
+    /// `openings` must have been calculated by `openings` for this board. The solver needs them
+    /// anyway, so they are passed in rather than calculated again.
+    fn heuristic_with(&self, openings: u64) -> u32 {
+        heuristic(openings, self.both)
+    }
+
+    /// Heuristic the solver uses to decide which moves to explore first. Higher means better for the
+    /// player who put in the last stone, i.e. the opponent of the player to move.
+    ///
+    /// It is the number of empty cells which would complete a four in a row for the player who put
+    /// in the last stone, no matter whether the cell can be played yet. So it ranges from `0` to the
+    /// number of empty cells. Useful for ordering moves in other searches, but it says nothing about
+    /// who wins. See [`Self::threats_after`] to evaluate a move of the player to move.
+    pub fn heuristic(&self) -> u32 {
+        self.heuristic_with(self.openings())
+    }
+
+    /// Bitmask with all positions which would imply victory for the player who did insert the last
+    /// stone, if they could place a stone in them.
+    fn openings(&self) -> u64 {
+        self.last.winning_positions()
     }
 
     /// Number of stones in the board
@@ -145,48 +418,395 @@ impl ConnectFour {
         self.both.stones()
     }
 
-    /// `true` if the player which did insert the last stone has won the game.
+    /// Number of empty cells, i.e. the number of stones which can still be put in before the board
+    /// is full. [`TOTAL_CELLS`] minus [`Self::stones`] for the standard board.
+    pub fn remaining_moves(&self) -> u8 {
+        (W * H) as u8 - self.stones()
+    }
+
+    /// Number of stones in each column, from the leftmost to the rightmost one. A column is full at
+    /// `6` (`H`) stones.
+    pub fn heights(&self) -> [u8; W] {
+        std::array::from_fn(|column| self.both.height(column as u8))
+    }
+
+    /// Like [`Self::heights`], but as fraction of the cells in the column, from `0.` for an empty
+    /// column to `1.` for a full one. E.g. for drawing bars.
+    pub fn column_fill(&self) -> [f32; W] {
+        self.heights().map(|height| height as f32 / H as f32)
+    }
+
+    /// `true` if the player which did insert the last stone has won the game. Checked once by
+    /// [`Self::play`] and remembered, so this is cheap to call repeatedly.
     pub fn is_victory(&self) -> bool {
-        self.last.is_win()
+        self.won
+    }
+
+    /// Same as calling [`Self::is_victory`] for each of the `games`. Uses a check without branches,
+    /// which the compiler is free to vectorize over consecutive games.
+    pub fn victories(games: &[Self]) -> impl Iterator<Item = bool> + '_ {
+        games.iter().map(|game| game.last.is_win_branchless())
+    }
+
+    /// The player who completed a four in a row, or `None` if nobody has won (yet).
+    pub fn winner(&self) -> Option<Player> {
+        if !self.is_victory() {
+            None
+        } else if (self.stones() % 2 == 1) != self.players_swapped {
+            Some(Player::One)
+        } else {
+            Some(Player::Two)
+        }
     }
 
-    /// Uses the first 49 Bits to uniquely encode the board.
+    /// Every complete four in a row on the board, of either player. Cells are identified by
+    /// `(row, column)` like in [`Self::to_svg_highlighted`]. Longer lines are reported as several
+    /// overlapping fours. A position reached by legal moves can hold more than one, if the last
+    /// stone completed several lines at once.
+    pub fn all_winning_lines(&self) -> Vec<[(u8, u8); 4]> {
+        let grid = self.to_grid();
+        // Steps in (row, column) for vertical, horizontal, / diagonal and \ diagonal lines.
+        let directions = [(1, 0), (0, 1), (1, 1), (-1, 1)];
+        let mut lines = Vec::new();
+        for row in 0..H as i32 {
+            for column in 0..W as i32 {
+                for (row_step, column_step) in directions {
+                    let line: [(i32, i32); 4] = std::array::from_fn(|i| {
+                        (row + row_step * i as i32, column + column_step * i as i32)
+                    });
+                    let on_board = |&(row, column): &(i32, i32)| {
+                        (0..H as i32).contains(&row) && (0..W as i32).contains(&column)
+                    };
+                    if !line.iter().all(on_board) {
+                        continue;
+                    }
+                    let owner = grid[row as usize][column as usize];
+                    if owner.is_some()
+                        && line
+                            .iter()
+                            .all(|&(row, column)| grid[row as usize][column as usize] == owner)
+                    {
+                        lines.push(line.map(|(row, column)| (row as u8, column as u8)));
+                    }
+                }
+            }
+        }
+        lines
+    }
+
+    /// Uses the first 49 Bits (`W * (H + 1)` for other board sizes) to uniquely encode the board.
+    /// Boards which only differ in whether the players have been swapped share their encoding.
     pub fn encode(&self) -> u64 {
         self.last.key(self.both)
     }
 
-    /// `true` if the current player has winning moves available
-    pub fn can_win_in_next_move(&self) -> bool {
+    /// Bitmask of the cells a stone can land in with the next move, i.e. the lowest empty cell of
+    /// each column which is not full.
+    ///
+    /// This and the other bitmasks use the same layout as the solver internally: Bit
+    /// `(H + 1) * column + row` stands for the cell in `row` and `column`, with the bottom row and
+    /// the leftmost column being `0`. The bit on top of each column is always `0`. For the standard
+    /// board:
+    ///
+    /// ```text
+    /// .  .  .  .  .  .  .  TOP
+    /// 5 12 19 26 33 40 47
+    /// 4 11 18 25 32 39 46
+    /// 3 10 17 24 31 38 45
+    /// 2  9 16 23 30 37 44
+    /// 1  8 15 22 29 36 43
+    /// 0  7 14 21 28 35 42  BOTTOM
+    /// ```
+    pub fn possible_mask(&self) -> u64 {
+        self.both.possible()
+    }
+
+    /// Bitmask of the cells which would complete a four in a row for the player to move, see
+    /// [`Self::possible_mask`] for the layout. Includes cells which can not be played yet, because
+    /// the cell below is still empty, and cells already taken by the opponent. See
+    /// [`Self::threat_masks`] for the empty cells only.
+    pub fn winning_positions(&self) -> u64 {
         let mut current = self.last;
         current.flip(self.both);
-        self.both.possible() & current.winning_positions() != 0
+        current.winning_positions()
+    }
+
+    /// Same as [`Self::winning_positions`], but for the opponent, i.e. the player who put in the
+    /// last stone.
+    pub fn opponent_winning_positions(&self) -> u64 {
+        self.last.winning_positions()
+    }
+
+    /// Bitmasks of the empty cells which would complete a four in a row, for the player to move
+    /// first and for their opponent second. Layout as described for [`Self::possible_mask`]. Unlike
+    /// [`Self::winning_positions`] and [`Self::opponent_winning_positions`], cells already taken by
+    /// the other player are left out. Cells which can not be played yet, because the cell below is
+    /// empty, are included. Intersect with [`Self::possible_mask`] to get the immediate threats.
+    pub fn threat_masks(&self) -> (u64, u64) {
+        let empty = self.both.empty();
+        (
+            self.winning_positions() & empty,
+            self.opponent_winning_positions() & empty,
+        )
+    }
+
+    /// The board reflected at the middle column. Mirrored positions have the same score, and the
+    /// moves of one correspond to the mirrored moves of the other.
+    pub fn mirror(&self) -> Self {
+        Board {
+            last: self.last.mirror(),
+            both: self.both.mirror(),
+            players_swapped: self.players_swapped,
+            won: self.won,
+        }
+    }
+
+    /// Like [`Self::encode`], but identical for a board and its [mirror](Self::mirror). The smaller
+    /// of both encodings is used.
+    pub fn canonical_encode(&self) -> u64 {
+        // Each column is encoded independently of the others, so encoding the mirror is the same as
+        // mirroring the encoding.
+        self.encode().min(self.mirror().encode())
+    }
+
+    /// `true` if both boards are identical, or would be identical, if the stones of player one and
+    /// two were exchanged. Unlike `==`, this does not care which player owns which stones, and unlike
+    /// [`Self::canonical_encode`] mirrored boards do not count as identical.
+    pub fn same_pattern(&self, other: &Self) -> bool {
+        let mut swapped = other.last;
+        swapped.flip(other.both);
+        self.both == other.both && (self.last == other.last || self.last == swapped)
+    }
+
+    /// `true` if the current player has winning moves available
+    pub fn can_win_in_next_move(&self) -> bool {
+        self.possible_mask() & self.winning_positions() != 0
+    }
+
+    /// Number of empty cells which would complete a four in a row for the current player, after
+    /// they played `column`. Two or more often form a double attack, which the opponent can not
+    /// block. Only counts threats, it is not a score: Cells which can not be played yet count, too.
+    /// `None` if the move is illegal.
+    pub fn threats_after(&self, column: Column) -> Option<u8> {
+        let mut after = *self;
+        if !after.play(column) {
+            return None;
+        }
+        Some(after.heuristic() as u8)
+    }
+
+    /// `true` if playing `column` leaves the current player with two or more cells they could win
+    /// in with their next stone. The opponent can block only one of them. `false` for illegal moves
+    /// and for moves which win right away, since the game is over then.
+    pub fn creates_fork(&self, column: Column) -> bool {
+        let mut after = *self;
+        if !after.play(column) || after.is_victory() {
+            return false;
+        }
+        let playable_threats = after.possible_mask() & after.opponent_winning_positions();
+        playable_threats.count_ones() >= 2
+    }
+
+    /// `true` if playing `column` completes a four in a row for the current player. `false` for
+    /// illegal moves.
+    pub fn is_winning_move(&self, column: Column) -> bool {
+        if !self.is_legal_move(column) {
+            return false;
+        }
+        // Cell the stone would land in
+        let landing = self.possible_mask() & bitboard::column::<H>(column.0);
+        landing & self.winning_positions() != 0
     }
 
     /// `true` if game has a winner or is a draw.
     pub fn is_over(&self) -> bool {
-        self.stones() == 42 || self.is_victory()
+        self.is_full() || self.is_victory()
+    }
+
+    /// `true` if neither player can complete a four in a row anymore, because each line still open
+    /// to one player is blocked by a stone of the other. The game is bound to end in a draw then,
+    /// no matter how the remaining cells are filled. Also `true` for full boards without a winner.
+    pub fn is_dead(&self) -> bool {
+        let mut current = self.last;
+        current.flip(self.both);
+        !self.last.can_still_win(self.both) && !current.can_still_win(self.both)
+    }
+
+    /// `true` if no four in a row through the cell at `row` and `column` can be completed anymore,
+    /// by either player, because each of these lines holds stones of both. The bottom row and the
+    /// leftmost column are `0`. Whatever happens in the cell can no longer decide the game. Panics
+    /// if the cell is not on the board.
+    pub fn is_dead_cell(&self, row: u8, column: u8) -> bool {
+        assert!((row as usize) < H && (column as usize) < W);
+        let mut current = self.last;
+        current.flip(self.both);
+        let live = self.last.live_cells(self.both) | current.live_cells(self.both);
+        live & bitboard::cell::<H>(row, column) == 0
+    }
+
+    /// `true` if the board is full and nobody has won.
+    pub fn is_draw(&self) -> bool {
+        self.is_full() && !self.is_victory()
+    }
+
+    /// `true` if every cell holds a stone.
+    fn is_full(&self) -> bool {
+        self.stones() as usize == W * H
+    }
+
+    /// Keeps playing the moves chosen by `policy` until the game is over and returns the result.
+    /// `policy` is called with the current board each time a stone needs to be put in. If the
+    /// policy picks a column which is full, the play out stops and the column is returned as
+    /// error. The board keeps all stones played up to this point.
+    pub fn play_out<R: FnMut(&Self) -> Column>(
+        &mut self,
+        mut policy: R,
+    ) -> Result<GameResult, Column> {
+        while !self.is_over() {
+            let column = policy(self);
+            if !self.play(column) {
+                return Err(column);
+            }
+        }
+        Ok(self
+            .winner()
+            .map(GameResult::Victory)
+            .unwrap_or(GameResult::Draw))
+    }
+
+    /// Legal moves after which the opponent can win with their next stone. These are all the legal
+    /// moves, which are not considered by the solver as non loosing moves.
+    ///
+    /// Panics if [`Self::can_win_in_next_move`] is `true`, since the current player would rather
+    /// win than worry about the next stone of the opponent.
+    pub fn loosing_moves(&self) -> impl Iterator<Item = Column> + use<'_, W, H> {
+        assert!(
+            !self.can_win_in_next_move(),
+            "Loosing moves are undefined if the current player can win with the next stone."
+        );
+        let non_loosing_moves = self.non_loosing_moves();
+        self.legal_moves()
+            .filter(move |column| !non_loosing_moves.contains(column.0))
+    }
+
+    /// Columns the current player has to play in, in order to stop the opponent from winning with
+    /// their next stone. Unlike [`Self::loosing_moves`], this does not care whether the stone would
+    /// allow the opponent to win on top of it. Empty if the opponent can not win with their next
+    /// stone, so there is nothing to block, but also if they can win in two or more cells at once,
+    /// since blocking one of them is not enough.
+    pub fn blocking_moves(&self) -> impl Iterator<Item = Column> + use<'_, W, H> {
+        let threats = self.opponent_winning_positions() & self.possible_mask();
+        let blockable = threats.count_ones() == 1;
+        (0..W as u8)
+            .filter(move |&index| blockable && threats & bitboard::column::<H>(index) != 0)
+            .map(Column::from_index)
+    }
+
+    /// `true` if the opponent could win with their next stone in two or more columns. The current
+    /// player can block only one of them, so unless they win right away, the game is lost and every
+    /// move counts among the [`Self::loosing_moves`]. `false` if the game is over.
+    pub fn opponent_has_double_threat(&self) -> bool {
+        // The possible mask holds at most one cell per column.
+        let threats = self.opponent_winning_positions() & self.possible_mask();
+        !self.is_over() && threats.count_ones() >= 2
+    }
+
+    /// `Some` column, if it is the only move which does not allow the opponent to win with their next
+    /// stone. `None` if there are several such moves, or none at all. Also `None` if the game is
+    /// over, or the current player can win with the next stone.
+    pub fn forced_move(&self) -> Option<Column> {
+        if self.is_over() || self.can_win_in_next_move() {
+            return None;
+        }
+        let non_loosing_moves = self.non_loosing_moves();
+        let mut columns = (0..W as u8).filter(|&index| non_loosing_moves.contains(index));
+        match (columns.next(), columns.next()) {
+            (Some(index), None) => Some(Column(index)),
+            _ => None,
+        }
     }
 
     // Only valid to call if `can_win_in_next_move` is `false`.
-    fn non_loosing_moves(&self) -> NonLoosingMoves {
+    fn non_loosing_moves(&self) -> NonLoosingMoves<H> {
+        self.non_loosing_moves_with(self.openings())
+    }
+
+    // Same as `non_loosing_moves`, but reuses `openings` which must have been calculated by
+    // `openings` for this board.
+    fn non_loosing_moves_with(&self, openings: u64) -> NonLoosingMoves<H> {
         debug_assert!(!self.can_win_in_next_move());
-        NonLoosingMoves::new(self.last, self.both)
+        debug_assert_eq!(openings, self.openings());
+        NonLoosingMoves::new(openings, self.both)
+    }
+}
+
+impl<const W: usize, const H: usize> PartialEq for Board<W, H> {
+    fn eq(&self, other: &Self) -> bool {
+        self.encode() == other.encode()
+    }
+}
+
+impl<const W: usize, const H: usize> Eq for Board<W, H> {}
+
+impl<const W: usize, const H: usize> Hash for Board<W, H> {
+    fn hash<S: Hasher>(&self, state: &mut S) {
+        self.encode().hash(state)
+    }
+}
+
+/// Wraps a board, so it compares and hashes equal to its mirror image. Use it as a key in a
+/// `HashMap` or `HashSet` in order to treat symmetric positions as one. See
+/// [`Board::canonical_encode`].
+#[derive(Clone, Copy)]
+pub struct CanonicalBoard<const W: usize = 7, const H: usize = 6>(pub Board<W, H>);
+
+impl<const W: usize, const H: usize> PartialEq for CanonicalBoard<W, H> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.canonical_encode() == other.0.canonical_encode()
+    }
+}
+
+impl<const W: usize, const H: usize> Eq for CanonicalBoard<W, H> {}
+
+impl<const W: usize, const H: usize> Hash for CanonicalBoard<W, H> {
+    fn hash<S: Hasher>(&self, state: &mut S) {
+        self.0.canonical_encode().hash(state)
     }
 }
 
-impl fmt::Display for ConnectFour {
+impl<const W: usize, const H: usize> From<Board<W, H>> for CanonicalBoard<W, H> {
+    fn from(board: Board<W, H>) -> Self {
+        CanonicalBoard(board)
+    }
+}
+
+/// Prints the board as a grid, like [`Board::print_to`]. The alternate flag (`{:#}`) prints it on a
+/// single line instead, e.g. for log files: One character for each cell, the top row first and each
+/// row from left to right. `X` stands for a stone of player one, `O` for player two and `.` for an
+/// empty cell.
+impl<const W: usize, const H: usize> fmt::Display for Board<W, H> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for row in (0..6).rev() {
-            for field in (0..7).map(|column| self.cell(row, column)) {
-                let c = match field {
-                    Cell::PlayerOne => 'X',
-                    Cell::PlayerTwo => 'O',
-                    Cell::Empty => ' ',
-                };
-                write!(f, "|{}", c)?;
+        if f.alternate() {
+            for row in (0..H as u8).rev() {
+                for column in 0..W as u8 {
+                    let c = match self.cell(row, column) {
+                        Cell::PlayerOne => 'X',
+                        Cell::PlayerTwo => 'O',
+                        Cell::Empty => '.',
+                    };
+                    write!(f, "{c}")?;
+                }
             }
-            writeln!(f, "|")?;
+            return Ok(());
         }
-        writeln!(f, "---------------\n 0 1 2 3 4 5 6")
+        fmt::Display::fmt(
+            &render::WithSymbols {
+                board: self,
+                one: 'X',
+                two: 'O',
+                empty: ' ',
+            },
+            f,
+        )
     }
 }
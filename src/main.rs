@@ -1,77 +1,370 @@
-use std::io::{stdin, stdout, self, BufRead};
-
-use connect_four_solver::{ConnectFour, Solver};
-
-fn main() -> io::Result<()>{
-    println!("\
-        Place a stone in the connect four board by typing the column number 1-7. Press s to
-        calculate score of current position. Use `p` to pick the first best move.");
-
-    let mut game = ConnectFour::new();
-    let mut input = stdin().lock();
-    let mut line = String::new();
-    let mut solver = Solver::new();
-
-    while !game.is_over() {
-        game.print_to(stdout())?;
-
-        line.clear();
-        input.read_line(&mut line)?;
-        let line = line.trim();
-        if line == "s"{
-            print_scores(game, &mut solver);
-            continue;
-        }
-        if line == "p" {
-            let mut best_moves = Vec::new();
-            solver.best_moves(&game, &mut best_moves);
-            if let Some(&col) = best_moves.first() {
-                game.play(col);
-            } else {
-                println!("No legal moves left.");
-            }
-            continue;
-        }
-        if let Ok(col) = line.parse() {
-            game.play(col);
-        } else {
-            println!("Invalid column.");
-            continue;
-        };
-    }
-    game.print_to(stdout())?;
-
-    Ok(())
-}
-
-fn print_scores(game: ConnectFour, solver: &mut Solver) {
-    for col in game.legal_moves() {
-        let mut game_copy = game;
-        if game_copy.play(col) {
-            let score = solver.score(&game_copy);
-            let stones_to_end = stones_to_end(game.stones() as i8, score);
-            let result_msg = match score.signum() {
-                0 => "Draw",
-                1 => "Loss",
-                -1 => "Win",
-                _ => unreachable!()
-            };
-            println!("{col}: {result_msg} in {stones_to_end} stones.");
-        }
-    }
-}
-
-fn stones_to_end(current_turn: i8, score: i8) -> i8 {
-    if score == 0 {
-        return 42 - current_turn;
-    }
-
-    let remaining_stones_at_end = score.abs() - 1;
-    let remaining_stones_now = 42 / 2 - current_turn / 2;
-    let stones_for_winner_to_throw = remaining_stones_now - remaining_stones_at_end;
-    if score.is_positive() {
-        stones_for_winner_to_throw * 2
-    } else {
-        (stones_for_winner_to_throw - 1) * 2 + 1
-    }
-}
\ No newline at end of file
+use std::{
+    env, fs,
+    io::{stdin, stdout, self, BufRead, Write},
+    process,
+};
+
+use connect_four_solver::{
+    interpret_score, verify_dataset, Column, ConnectFour, Outcome, Player, RenderStyle, Solver,
+};
+
+/// Size of the transposition table if `--table-size` is not specified. Same as [`Solver::new`].
+const DEFAULT_TABLE_SIZE_MIB: usize = 64;
+
+fn main() -> io::Result<()>{
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    // `--table-size <MiB>` trades memory for speed. It may appear anywhere, so it is removed from
+    // `args` before looking at the subcommands.
+    let mut solver = match solver_from_args(&mut args) {
+        Ok(solver) => solver,
+        Err(message) => {
+            eprintln!("{message}");
+            process::exit(2);
+        }
+    };
+
+    // `solve` reads positions from standard input and prints their scores, without prompting.
+    if args.first().map(String::as_str) == Some("solve") {
+        return solve(solver);
+    }
+
+    // `verify <path>` scores the positions of a dataset and reports the ones scored differently.
+    if args.first().map(String::as_str) == Some("verify") {
+        let Some(path) = args.get(1) else {
+            eprintln!("`verify` expects the path of a dataset.");
+            process::exit(2);
+        };
+        return verify(path, solver);
+    }
+
+    // `review <moves>` replays a game and tells, for each move, how much worse it is than the best
+    // one.
+    if args.first().map(String::as_str) == Some("review") {
+        let Some(moves) = args.get(1).filter(|moves| parse_move_list(moves).is_some()) else {
+            eprintln!("`review` expects a valid move list.");
+            process::exit(2);
+        };
+        return review(moves, solver);
+    }
+
+    // With `--json` every output is a single line JSON object, so the binary can be driven by
+    // scripts.
+    let json = args.iter().any(|arg| arg == "--json");
+
+    if !json {
+        println!("\
+            Place a stone in the connect four board by typing the column number 1-7. Press s to
+            calculate score of current position. Use `p` to pick the first best move, `h` to mark
+            all of the best moves and `u` to take back the last one.
+            Type `save <path>` to store the moves played so far in a file, `load <path>` to resume.
+            Run with `solve` to score move lists read from standard input, one per line.
+            Run with `verify <path>` to check a file of move lists and their expected scores.
+            Run with `review <moves>` to compare each move of a game with the best ones.
+            Use `--table-size <MiB>` to set the memory used by the solver (default: \
+            {DEFAULT_TABLE_SIZE_MIB} MiB).");
+    }
+
+    let mut game = ConnectFour::new();
+    // Moves played so far, e.g. "4453". Written by `save`.
+    let mut moves = String::new();
+    let mut input = stdin().lock();
+    let mut line = String::new();
+
+    while !game.is_over() {
+        print_board(&game, json)?;
+
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            // End of input
+            return Ok(());
+        }
+        let line = line.trim();
+        if line == "s"{
+            print_scores(game, &mut solver, json)?;
+            continue;
+        }
+        if line == "p" {
+            let mut best_moves = Vec::new();
+            solver.best_moves(&game, &mut best_moves);
+            if let Some(&col) = best_moves.first() {
+                game.play(col);
+                moves.push_str(&col.to_string());
+            } else {
+                print_error("No legal moves left.", json);
+            }
+            continue;
+        }
+        if line == "h" {
+            print_hint(&game, &mut solver, json)?;
+            continue;
+        }
+        if line == "u" {
+            if moves.pop().is_some() {
+                // The board can not take back stones, so replay all moves but the last one.
+                game = parse_move_list(&moves).expect("Moves played so far must be legal");
+            } else {
+                print_error("No moves to undo.", json);
+            }
+            continue;
+        }
+        if let Some(path) = line.strip_prefix("save ") {
+            if let Err(error) = fs::write(path.trim(), format!("{moves}\n")) {
+                print_error(&format!("Could not save game: {error}"), json);
+            }
+            continue;
+        }
+        if let Some(path) = line.strip_prefix("load ") {
+            match load(path.trim()) {
+                Ok((loaded_game, loaded_moves)) => {
+                    game = loaded_game;
+                    moves = loaded_moves;
+                }
+                Err(message) => print_error(&message, json),
+            }
+            continue;
+        }
+        if let Ok(col) = line.parse() {
+            if let Err(error) = game.try_play(col) {
+                print_error(&error.to_string(), json);
+            } else {
+                moves.push_str(&col.to_string());
+            }
+        } else {
+            print_error("Invalid column.", json);
+            continue;
+        };
+    }
+    print_board(&game, json)?;
+
+    Ok(())
+}
+
+/// Reads one move list per line from standard input and prints `<moves> <score>` for each of them.
+/// This is the same format the integration tests use. Anything after the move list on a line is
+/// ignored. Invalid lines are reported on standard error and skipped.
+fn solve(mut solver: Solver) -> io::Result<()> {
+    let mut out = stdout().lock();
+    for line in stdin().lock().lines() {
+        let line = line?;
+        let Some(moves) = line.split_whitespace().next() else {
+            continue;
+        };
+        let Some(game) = parse_move_list(moves) else {
+            eprintln!("Invalid move list: {moves}");
+            continue;
+        };
+        writeln!(out, "{moves} {}", solver.score(&game))?;
+    }
+    Ok(())
+}
+
+/// Prints each position of the dataset at `path`, which is scored differently than expected. Exits
+/// with status `1` if there is any.
+fn verify(path: &str, mut solver: Solver) -> io::Result<()> {
+    let mismatches = verify_dataset(path, &mut solver)?;
+    for (moves, expected, actual) in &mismatches {
+        println!("{moves}: expected {expected}, got {actual}");
+    }
+    if !mismatches.is_empty() {
+        eprintln!("{} positions scored differently than expected.", mismatches.len());
+        process::exit(1);
+    }
+    println!("All positions scored as expected.");
+    Ok(())
+}
+
+/// Prints for each move of the game `moves`, the best moves in its position and how many points
+/// of score it gave away compared to them. A move giving away points is a mistake, one changing the
+/// outcome of the game from a win to a draw or loss, or from a draw to a loss, a blunder.
+fn review(moves: &str, mut solver: Solver) -> io::Result<()> {
+    let mut out = stdout().lock();
+    let mut game = ConnectFour::new();
+    for (ply, c) in moves.chars().enumerate() {
+        let col: Column = c.to_string().parse().expect("Move list has been validated");
+        // Score of each legal move, best first. Equally good moves are listed in the order the
+        // solver prefers them.
+        let scores = solver.ranked_moves(&game);
+        let (_, best) = *scores.first().expect("Game is not over");
+        let (_, played) = *scores
+            .iter()
+            .find(|&&(column, _)| column == col)
+            .expect("Legal move");
+        let best_moves: Vec<String> = scores
+            .iter()
+            .filter(|&&(_, score)| score == best)
+            .map(|(column, _)| column.to_string())
+            .collect();
+        let verdict = if played.signum() < best.signum() {
+            " Blunder!"
+        } else if played < best {
+            " Mistake."
+        } else {
+            ""
+        };
+        writeln!(
+            out,
+            "{:>2}. {col}: best {}, lost {}.{verdict}",
+            ply + 1,
+            best_moves.join(" "),
+            best - played
+        )?;
+        game.play(col);
+    }
+    Ok(())
+}
+
+/// Solver with the table size given by `--table-size <MiB>`, if present. Removes the flag and its
+/// value from `args`.
+fn solver_from_args(args: &mut Vec<String>) -> Result<Solver, String> {
+    let Some(position) = args.iter().position(|arg| arg == "--table-size") else {
+        return Ok(Solver::new());
+    };
+    let mib: usize = args
+        .get(position + 1)
+        .and_then(|value| value.parse().ok())
+        .filter(|&mib| mib > 0)
+        .ok_or("`--table-size` expects a positive number of MiB.")?;
+    args.drain(position..position + 2);
+    let capacity = capacity_for(mib).ok_or("`--table-size` is too large.")?;
+    Ok(Solver::with_capacity(capacity))
+}
+
+/// Largest prime number of buckets which fits into `mib` MiB. Each bucket takes 16 Byte. Primes
+/// spread the positions evenly over the buckets. `None` if the number of bytes overflows.
+fn capacity_for(mib: usize) -> Option<usize> {
+    let buckets = mib.checked_mul(1 << 20)? / 16;
+    Some((2..=buckets).rev().find(|&n| is_prime(n)).unwrap_or(1))
+}
+
+fn is_prime(n: usize) -> bool {
+    (2..).take_while(|divisor| divisor * divisor <= n).all(|divisor| !n.is_multiple_of(divisor))
+}
+
+/// Reads a game stored by the `save` command. Returns the game and its moves, or a message telling
+/// why the file could not be loaded.
+fn load(path: &str) -> Result<(ConnectFour, String), String> {
+    let content =
+        fs::read_to_string(path).map_err(|error| format!("Could not load game: {error}"))?;
+    let moves = content.trim();
+    let game = parse_move_list(moves).ok_or("File does not contain a valid move list.")?;
+    Ok((game, moves.to_owned()))
+}
+
+/// Like [`ConnectFour::from_move_list`], but returns `None` instead of panicking for invalid or
+/// illegal moves.
+fn parse_move_list(moves: &str) -> Option<ConnectFour> {
+    let mut game = ConnectFour::new();
+    for c in moves.chars() {
+        let col = c.to_string().parse().ok()?;
+        if game.is_over() || !game.play(col) {
+            return None;
+        }
+    }
+    Some(game)
+}
+
+fn print_board(game: &ConnectFour, json: bool) -> io::Result<()> {
+    if !json {
+        return game.print_to(stdout());
+    }
+    // Reuse the text representation, without the `|` separators and the column numbers.
+    let mut text = Vec::new();
+    game.print_to(&mut text)?;
+    let text = String::from_utf8(text).unwrap();
+    let rows: Vec<String> = text
+        .lines()
+        .take(6)
+        .map(|row| format!("\"{}\"", row.chars().skip(1).step_by(2).collect::<String>()))
+        .collect();
+    let winner = match game.winner() {
+        Some(Player::One) => "1",
+        Some(Player::Two) => "2",
+        None => "null",
+    };
+    println!(
+        "{{\"rows\":[{}],\"stones\":{},\"over\":{},\"winner\":{winner}}}",
+        rows.join(","),
+        game.stones(),
+        game.is_over(),
+    );
+    Ok(())
+}
+
+/// Prints the outcome of each legal move. Moves are scored one after the other, and each result is
+/// printed as soon as it is known, so slow positions do not freeze the prompt until all of them are
+/// done.
+fn print_scores(game: ConnectFour, solver: &mut Solver, json: bool) -> io::Result<()> {
+    let mut out = stdout().lock();
+    if json {
+        write!(out, "{{")?;
+    }
+    let children = game.children().filter(|_| !game.is_over());
+    for (index, (col, child)) in children.enumerate() {
+        let score = -solver.score(&child);
+        let (outcome, stones_to_end) = interpret_score(&game, score);
+        if json {
+            let outcome = match outcome {
+                Outcome::Win => "win",
+                Outcome::Draw => "draw",
+                Outcome::Loss => "loss",
+            };
+            let separator = if index == 0 { "" } else { "," };
+            write!(
+                out,
+                "{separator}\"{col}\":{{\"outcome\":\"{outcome}\",\"stones\":{stones_to_end}}}"
+            )?;
+        } else {
+            let result_msg = match outcome {
+                Outcome::Draw => "Draw",
+                Outcome::Loss => "Loss",
+                Outcome::Win => "Win",
+            };
+            writeln!(out, "{col}: {result_msg} in {stones_to_end} stones.")?;
+        }
+        out.flush()?;
+    }
+    if json {
+        writeln!(out, "}}")?;
+    }
+    Ok(())
+}
+
+/// Prints the board with the best moves marked below it.
+fn print_hint(game: &ConnectFour, solver: &mut Solver, json: bool) -> io::Result<()> {
+    let mut best_moves = Vec::new();
+    solver.best_moves(game, &mut best_moves);
+    if json {
+        let columns: Vec<String> = best_moves.iter().map(Column::to_string).collect();
+        println!("{{\"best_moves\":[{}]}}", columns.join(","));
+        return Ok(());
+    }
+    game.render_marked(RenderStyle::Ascii, &best_moves, stdout())
+}
+
+fn print_error(message: &str, json: bool) {
+    if json {
+        println!("{{\"error\":{}}}", json_string(message));
+    } else {
+        println!("{message}");
+    }
+}
+
+/// `text` as a quoted JSON string. Messages of I/O errors may contain quotes, backslashes or
+/// control characters, which must be escaped.
+fn json_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
@@ -0,0 +1,173 @@
+use std::fmt;
+
+use crate::{Board, Column, ConnectFour, PlayError};
+
+/// How the moves of a game are written down. See [`Board::from_notation`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NotationFormat {
+    /// One digit per move without separators, the leftmost column being `1`, e.g. `"4453"`. This is
+    /// the format of [`Board::from_move_list`].
+    Packed1Indexed,
+    /// Moves separated by whitespace, the leftmost column being `1`, e.g. `"4 4 5 3"`.
+    Spaced1Indexed,
+    /// Moves separated by commas, the leftmost column being `0`, e.g. `"3,3,4,2"`.
+    Comma0Indexed,
+}
+
+/// Reason why [`Board::from_notation`] or [`PlayedGame::from_bytes`] could not replay a game.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NotationError {
+    /// The move at `index` is not a column number of the format.
+    InvalidToken { index: usize },
+    /// The move at `index` is a column number, but can not be played.
+    IllegalMove { index: usize, error: PlayError },
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NotationError::InvalidToken { index } => {
+                write!(f, "Move {} is not a column.", index + 1)
+            }
+            NotationError::IllegalMove { index, error } => {
+                write!(f, "Move {} is illegal: {error}", index + 1)
+            }
+        }
+    }
+}
+
+impl std::error::Error for NotationError {}
+
+impl<const W: usize, const H: usize> Board<W, H> {
+    /// Replays the moves in `notation` written in `format`, starting with an empty board. Whitespace
+    /// around the moves is ignored. Fails on the first move which is not a number, refers to a
+    /// column beyond the width of the board, or is illegal otherwise.
+    pub fn from_notation(notation: &str, format: NotationFormat) -> Result<Self, NotationError> {
+        let tokens: Vec<&str> = match format {
+            NotationFormat::Packed1Indexed => notation
+                .split(char::is_whitespace)
+                .flat_map(|chunk| chunk.split_inclusive(|_| true))
+                .collect(),
+            NotationFormat::Spaced1Indexed => notation.split_whitespace().collect(),
+            NotationFormat::Comma0Indexed if notation.trim().is_empty() => Vec::new(),
+            NotationFormat::Comma0Indexed => notation.split(',').map(str::trim).collect(),
+        };
+        let offset = match format {
+            NotationFormat::Packed1Indexed | NotationFormat::Spaced1Indexed => 1,
+            NotationFormat::Comma0Indexed => 0,
+        };
+        let mut game = Self::new();
+        for (index, token) in tokens.into_iter().enumerate() {
+            // `parse` would also accept a leading `+`.
+            let number: usize = Some(token)
+                .filter(|token| token.bytes().all(|byte| byte.is_ascii_digit()))
+                .and_then(|token| token.parse().ok())
+                .filter(|&number| number >= offset)
+                .ok_or(NotationError::InvalidToken { index })?;
+            let column = number - offset;
+            if column >= W {
+                let error = PlayError::NoSuchColumn;
+                return Err(NotationError::IllegalMove { index, error });
+            }
+            game.try_play(Column::from_index(column as u8))
+                .map_err(|error| NotationError::IllegalMove { index, error })?;
+        }
+        Ok(game)
+    }
+}
+
+/// Record of a game on the standard board: the moves in the order they have been played, together
+/// with the board they lead to. Unlike [`ConnectFour::encode`] this keeps the order of the moves.
+/// Every move of the record is legal, since moves are only added by playing them.
+#[derive(Clone, Default, PartialEq)]
+pub struct PlayedGame {
+    moves: Vec<Column>,
+    board: ConnectFour,
+}
+
+impl PlayedGame {
+    /// Record of a game which has not started yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Plays `column` like [`ConnectFour::try_play`] and appends it to the moves, if it is legal.
+    pub fn try_play(&mut self, column: Column) -> Result<(), PlayError> {
+        self.board.try_play(column)?;
+        self.moves.push(column);
+        Ok(())
+    }
+
+    /// Plays `moves` one by one on an empty board, calling `visit` with the board after each of
+    /// them and the move just played. E.g. to animate a recorded game or to annotate each position.
+    /// Returns the record of the game, or stops at the first illegal move like
+    /// [`ConnectFour::play_all`]. `visit` has been called for each move before it then. Step
+    /// through a decoded game with `PlayedGame::replay(game.moves(), visit)`.
+    pub fn replay(
+        moves: &[Column],
+        mut visit: impl FnMut(&ConnectFour, Column),
+    ) -> Result<Self, (usize, PlayError)> {
+        let mut game = Self::new();
+        for (index, &column) in moves.iter().enumerate() {
+            game.try_play(column).map_err(|error| (index, error))?;
+            visit(&game.board, column);
+        }
+        Ok(game)
+    }
+
+    /// Moves played so far, the first one first.
+    pub fn moves(&self) -> &[Column] {
+        &self.moves
+    }
+
+    /// Board after the last move.
+    pub fn board(&self) -> &ConnectFour {
+        &self.board
+    }
+
+    /// Packs the moves into three bits each, so a complete game fits into 16 bytes. The first move
+    /// takes the lowest three bits of the first byte. Unused bits of the last byte are set to `1`,
+    /// which is no column. Read the game back with [`Self::from_bytes`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![u8::MAX; (self.moves.len() * 3).div_ceil(8)];
+        for (index, column) in self.moves.iter().enumerate() {
+            for bit in 0..3 {
+                let position = index * 3 + bit;
+                if column.0 >> bit & 1 == 0 {
+                    bytes[position / 8] &= !(1 << (position % 8));
+                }
+            }
+        }
+        bytes
+    }
+
+    /// Game packed by [`Self::to_bytes`]. Replays the moves on an empty board to make sure they
+    /// form a legal game. Fails for the first move which is illegal, or not a column, unless only
+    /// padding follows. Fails, too, if any bit of the padding is not set.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, NotationError> {
+        let bit = |position: usize| bytes[position / 8] >> (position % 8) & 1;
+        let num_bits = bytes.len() * 8;
+        let mut game = Self::new();
+        for index in 0..num_bits / 3 {
+            let column = (0..3).fold(0, |column, offset| {
+                column | bit(index * 3 + offset) << offset
+            });
+            if column == 7 {
+                // Padding must not span more than the last byte.
+                let rest = index * 3..num_bits;
+                if rest.len() >= 8 || !rest.into_iter().all(|position| bit(position) == 1) {
+                    return Err(NotationError::InvalidToken { index });
+                }
+                break;
+            }
+            game.try_play(Column::from_index(column))
+                .map_err(|error| NotationError::IllegalMove { index, error })?;
+        }
+        // Bits too few to hold another move are padding, too.
+        let index = num_bits / 3;
+        if !(index * 3..num_bits).all(|position| bit(position) == 1) {
+            return Err(NotationError::InvalidToken { index });
+        }
+        Ok(game)
+    }
+}
@@ -0,0 +1,33 @@
+use crate::ConnectFour;
+
+/// Stage of a game, judged by the number of stones on the board. See [`ConnectFour::phase`].
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Phase {
+    /// Fewer than [`Phase::MIDGAME_STONES`] stones. Positions with few stones are the hardest to
+    /// search, but the ones with the fewest are precalculated (see [`crate::precalculated_depth`]).
+    Opening,
+    /// From [`Phase::MIDGAME_STONES`] stones, up to [`Phase::ENDGAME_STONES`] (exclusive).
+    Midgame,
+    /// [`Phase::ENDGAME_STONES`] stones or more. Scored in a fraction of a millisecond.
+    Endgame,
+}
+
+impl Phase {
+    /// Number of stones the midgame starts with.
+    pub const MIDGAME_STONES: u8 = 15;
+    /// Number of stones the endgame starts with.
+    pub const ENDGAME_STONES: u8 = 29;
+}
+
+impl ConnectFour {
+    /// Stage of the game, e.g. to choose search parameters or to label positions. The thresholds
+    /// ([`Phase::MIDGAME_STONES`] and [`Phase::ENDGAME_STONES`]) are the ones of the test sets of
+    /// this crate, which group positions into beginning, middle and end of a game in the same way.
+    pub fn phase(&self) -> Phase {
+        match self.stones() {
+            stones if stones >= Phase::ENDGAME_STONES => Phase::Endgame,
+            stones if stones >= Phase::MIDGAME_STONES => Phase::Midgame,
+            _ => Phase::Opening,
+        }
+    }
+}
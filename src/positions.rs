@@ -0,0 +1,170 @@
+use std::collections::HashSet;
+
+use crate::{Board, ConnectFour};
+
+/// All distinct positions with exactly `num_stones` stones, which can be reached from the empty
+/// board by legal play. Games end with a victory, so positions won with fewer stones are not played
+/// on. If `canonical` is `true`, a position and its mirror image count as one, and only one of them
+/// is returned. Positions are ordered by their key ([`ConnectFour::encode`], or
+/// [`ConnectFour::canonical_encode`] if `canonical`).
+pub fn unique_positions(num_stones: u8, canonical: bool) -> Vec<ConnectFour> {
+    ConnectFour::new().descendants(num_stones, canonical)
+}
+
+/// Number of [`unique_positions`] with exactly `num_stones` stones. Counting without `canonical`
+/// yields the well known number of Connect Four positions after `num_stones` plies (OEIS A212693):
+/// 1, 7, 49, 238, 1120, ...
+pub fn count_positions(num_stones: u8, canonical: bool) -> u64 {
+    unique_positions(num_stones, canonical).len() as u64
+}
+
+/// Set of positions, e.g. to skip duplicates while collecting positions from many games. A position
+/// and its mirror image count as one, since only their [`ConnectFour::canonical_encode`] is stored.
+/// Unlike a `HashSet` of [`crate::CanonicalBoard`]s, the boards themselves are not kept.
+#[derive(Clone, Default, Debug)]
+pub struct PositionSet {
+    keys: HashSet<u64>,
+}
+
+impl PositionSet {
+    /// Empty set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the position of `game`. `true` if neither it nor its mirror image has been in the set
+    /// before.
+    pub fn insert(&mut self, game: &ConnectFour) -> bool {
+        self.keys.insert(game.canonical_encode())
+    }
+
+    /// `true` if the position of `game`, or its mirror image, is in the set.
+    pub fn contains(&self, game: &ConnectFour) -> bool {
+        self.keys.contains(&game.canonical_encode())
+    }
+
+    /// Number of distinct positions in the set
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// `true` if no position has been inserted
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// The [`ConnectFour::canonical_encode`] of each position in the set, in no particular order.
+    pub fn keys(&self) -> impl Iterator<Item = u64> + '_ {
+        self.keys.iter().copied()
+    }
+}
+
+impl<const W: usize, const H: usize> Board<W, H> {
+    /// All distinct positions reached by playing exactly `plies` more stones, e.g. to collect
+    /// puzzles of a certain length. Like [`unique_positions`], but starting from this board rather
+    /// than the empty one, and for any board size: Won games are not played on, `canonical` counts
+    /// mirror images as one position, and the positions are ordered by their key.
+    ///
+    /// The number of positions grows by a factor of three to seven with each ply early in the game,
+    /// and all of them are held in memory. Starting from the empty board, eight plies yield about
+    /// 185,000 positions, ten plies more than a million. Keep `plies` in the single digits, unless
+    /// many cells are filled already.
+    pub fn descendants(&self, plies: u8, canonical: bool) -> Vec<Self> {
+        let key = |board: &Self| {
+            if canonical {
+                board.canonical_encode()
+            } else {
+                board.encode()
+            }
+        };
+        let mut boards = vec![*self];
+        for _ in 0..plies {
+            let mut next_boards: Vec<Self> = boards
+                .iter()
+                .filter(|board| !board.is_over())
+                .flat_map(|board| board.children().map(|(_, child)| child))
+                .collect();
+            next_boards.sort_by_key(key);
+            next_boards.dedup_by_key(|board| key(board));
+            boards = next_boards;
+        }
+        boards
+    }
+
+    /// Number of distinct move sequences of `plies` stones, starting from this board. Sequences
+    /// ending early, because a player wins or the board is full, count as well. Unlike
+    /// [`Self::descendants`], positions reached in different orders count once for each order, so
+    /// this is the number of leaves of the game tree.
+    ///
+    /// Every sequence is played out, and the number grows by a factor of up to seven with each ply:
+    /// 2,401 for four plies from the empty board, 823,536 for seven and more than a billion for
+    /// eleven. Keep `plies` small.
+    pub fn count_lines(&self, plies: u8) -> u64 {
+        if plies == 0 || self.is_over() {
+            return 1;
+        }
+        self.children()
+            .map(|(_, child)| child.count_lines(plies - 1))
+            .sum()
+    }
+}
+
+/// Whether [`ConnectFour::walk_tree`] descends into the children of a node.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Visit {
+    /// Visit the children, unless the game is over or the maximum depth is reached.
+    Children,
+    /// Skip the entire subtree below this node.
+    SkipChildren,
+}
+
+/// Notified by [`ConnectFour::walk_tree`] for each node of the game tree.
+pub trait TreeVisitor {
+    /// Called before any of the children of `game` are visited. `depth` is the number of stones
+    /// played since the root of the walk.
+    fn enter(&mut self, game: &ConnectFour, depth: u8) -> Visit;
+
+    /// Called after all the children of `game` have been visited, or right after [`Self::enter`]
+    /// if there are none to visit. Does nothing by default.
+    fn leave(&mut self, game: &ConnectFour, depth: u8) {
+        let _ = (game, depth);
+    }
+}
+
+enum Step {
+    Enter(ConnectFour, u8),
+    Leave(ConnectFour, u8),
+}
+
+impl ConnectFour {
+    /// Walks the game tree depth first, starting with this board and playing up to `max_depth`
+    /// stones. Children are visited in the order of their columns, from left to right. Won games
+    /// are not played on, and `visitor` may skip any subtree by returning [`Visit::SkipChildren`].
+    ///
+    /// Unlike [`Self::descendants`] this walks the tree rather than collecting distinct positions,
+    /// so positions reached by different move orders are visited once for each of them. The number
+    /// of nodes grows close to `7^max_depth`: about six million for eight stones, almost three
+    /// hundred million for ten. Only the nodes along the current path and their siblings are held
+    /// in memory though, and the walk does not recurse, so any depth is safe, given enough time.
+    pub fn walk_tree<V: TreeVisitor>(&self, visitor: &mut V, max_depth: u8) {
+        let mut stack = vec![Step::Enter(*self, 0)];
+        while let Some(step) = stack.pop() {
+            match step {
+                Step::Enter(game, depth) => {
+                    let visit = visitor.enter(&game, depth);
+                    stack.push(Step::Leave(game, depth));
+                    if visit == Visit::Children && depth < max_depth && !game.is_over() {
+                        let first_child = stack.len();
+                        stack.extend(
+                            game.children()
+                                .map(|(_, child)| Step::Enter(child, depth + 1)),
+                        );
+                        // Reversed, so the leftmost child is popped first.
+                        stack[first_child..].reverse();
+                    }
+                }
+                Step::Leave(game, depth) => visitor.leave(&game, depth),
+            }
+        }
+    }
+}
@@ -1,58 +1,130 @@
-//! Use the `precalculate` binary in order learn the constants to set here and generate the `
-//! scores.dat` file.`
-use crate::ConnectFour;
-
-/// `0` Would indicate that no preclaculated scores are available. If during the development cycle
-/// you messed up, and it does not compile because of invalid contents in `scores.dat`, you can set
-/// this to `0` in order to ignore precalculated scores.
-/// `1` indicates that up to one stones everything is precalculated, i.e. the first position of the
-/// board. `2` would indicate that up to two stones everything is precalculated, i.e. every board
-/// with one stone in it, and so on.
-const NUM_STONES_PRECALCULATED_UP_TO: u8 = 7;
-
-const PRECALCULATED_INPUT_BYTES: &[u8] = include_bytes!("./scores.dat");
-
-/// Number of unique postions with precalculated scores. Look at the ouput of preallocated to learn
-/// this number.
-const NUM_SCORES_PRECALCULATED: usize = PRECALCULATED_INPUT_BYTES.len() / (8 + 1);
-static PRECALCULATED: [(u64, i8); NUM_SCORES_PRECALCULATED] = load_precalculated();
-
-const fn load_precalculated() -> [(u64, i8); NUM_SCORES_PRECALCULATED] {
-    let input_bytes = PRECALCULATED_INPUT_BYTES;
-    let mut result = [(0, 0); NUM_SCORES_PRECALCULATED];
-    let mut index = 0;
-    let length = 8 + 1; // 8 bytes for the board, 1 byte for the score
-    loop {
-        if index == NUM_SCORES_PRECALCULATED {
-            break;
-        }
-        let encoded_board = u64::from_le_bytes([
-            input_bytes[index * length],
-            input_bytes[index * length + 1],
-            input_bytes[index * length + 2],
-            input_bytes[index * length + 3],
-            input_bytes[index * length + 4],
-            input_bytes[index * length + 5],
-            input_bytes[index * length + 6],
-            input_bytes[index * length + 7],
-        ]);
-        let score = input_bytes[index * length + 8] as i8;
-
-        result[index] = (encoded_board, score);
-        index += 1;
-    }
-    result
-}
-
-/// It can take seconds to minutes to calculate the score of a board with few stones in it. To
-/// keep it fast, we precalculated the scores for a bunch of boards. If there is a precalculated
-/// score for the board score is returned with `Some(score)`, otherwise `None` is returned.
-pub fn precalculated_score(board: &ConnectFour) -> Option<i8> {
-    if board.stones() >= NUM_STONES_PRECALCULATED_UP_TO {
-        return None;
-    }
-    let index = PRECALCULATED
-        .binary_search_by_key(&board.encode(), |(k, _)| *k)
-        .expect("Must be precalculated");
-    Some(PRECALCULATED[index].1)
-}
+//! Use the `precalculate` binary in order learn the constants to set here and generate the `
+//! scores.dat` file.`
+use crate::ConnectFour;
+
+/// `0` Would indicate that no preclaculated scores are available. If during the development cycle
+/// you messed up, and it does not compile because of invalid contents in `scores.dat`, you can set
+/// this to `0` in order to ignore precalculated scores.
+/// `1` indicates that up to one stones everything is precalculated, i.e. the first position of the
+/// board. `2` would indicate that up to two stones everything is precalculated, i.e. every board
+/// with one stone in it, and so on.
+const NUM_STONES_PRECALCULATED_UP_TO: u8 = 7;
+
+/// Boards with fewer stones than this are scored by looking up precalculated scores. Scoring boards
+/// with more stones requires a search, which may take long for boards with few stones beyond this
+/// number, e.g. to decide whether to show a progress indicator.
+pub const fn precalculated_depth() -> u8 {
+    NUM_STONES_PRECALCULATED_UP_TO
+}
+
+const PRECALCULATED_INPUT_BYTES: &[u8] = include_bytes!("./scores.dat");
+
+/// Number of unique postions with precalculated scores. Look at the ouput of preallocated to learn
+/// this number.
+const NUM_SCORES_PRECALCULATED: usize = PRECALCULATED_INPUT_BYTES.len() / (8 + 1);
+static PRECALCULATED: [(u64, i8); NUM_SCORES_PRECALCULATED] = load_precalculated();
+
+const fn load_precalculated() -> [(u64, i8); NUM_SCORES_PRECALCULATED] {
+    let input_bytes = PRECALCULATED_INPUT_BYTES;
+    let mut result = [(0, 0); NUM_SCORES_PRECALCULATED];
+    let mut index = 0;
+    let length = 8 + 1; // 8 bytes for the board, 1 byte for the score
+    loop {
+        if index == NUM_SCORES_PRECALCULATED {
+            break;
+        }
+        let encoded_board = u64::from_le_bytes([
+            input_bytes[index * length],
+            input_bytes[index * length + 1],
+            input_bytes[index * length + 2],
+            input_bytes[index * length + 3],
+            input_bytes[index * length + 4],
+            input_bytes[index * length + 5],
+            input_bytes[index * length + 6],
+            input_bytes[index * length + 7],
+        ]);
+        let score = input_bytes[index * length + 8] as i8;
+
+        result[index] = (encoded_board, score);
+        index += 1;
+    }
+    result
+}
+
+/// It can take seconds to minutes to calculate the score of a board with few stones in it. To
+/// keep it fast, we precalculated the scores for a bunch of boards. If there is a precalculated
+/// score for the board score is returned with `Some(score)`, otherwise `None` is returned.
+///
+/// A board and its mirror image have the same score, so only one of them is stored, keyed by
+/// [`ConnectFour::canonical_encode`]. Boards which can not occur in a real game, e.g. built with
+/// [`crate::ConnectFourBuilder`] or changed with [`ConnectFour::pop`], are not stored either.
+pub fn precalculated_score(board: &ConnectFour) -> Option<i8> {
+    if board.stones() >= NUM_STONES_PRECALCULATED_UP_TO {
+        return None;
+    }
+    PRECALCULATED
+        .binary_search_by_key(&board.canonical_encode(), |(k, _)| *k)
+        .ok()
+        .map(|index| PRECALCULATED[index].1)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{unique_positions, ConnectFour, Solver};
+
+    use super::{precalculated_score, NUM_SCORES_PRECALCULATED, NUM_STONES_PRECALCULATED_UP_TO};
+
+    #[test]
+    fn mirrored_boards_share_score() {
+        // Mirrored positions used to be stored separately, and the score of "777777" has been
+        // wrong.
+        let game = ConnectFour::from_move_list("111111");
+        let mirrored = ConnectFour::from_move_list("777777");
+
+        assert_eq!(Some(1), precalculated_score(&game));
+        assert_eq!(Some(1), precalculated_score(&mirrored));
+    }
+
+    #[test]
+    fn every_reachable_position_is_precalculated() {
+        let positions: usize = (0..NUM_STONES_PRECALCULATED_UP_TO)
+            .map(|stones| unique_positions(stones, true).len())
+            .sum();
+
+        assert_eq!(NUM_SCORES_PRECALCULATED, positions);
+    }
+
+    /// The score of each position must be the best score of its children, from the perspective of
+    /// the player to move. Checks every precalculated score, except the ones with the most stones,
+    /// whose children are not precalculated. The empty board used to fail this.
+    #[test]
+    fn precalculated_scores_agree_with_their_children() {
+        for stones in 0..NUM_STONES_PRECALCULATED_UP_TO - 1 {
+            for game in unique_positions(stones, true) {
+                let best_child = game
+                    .children()
+                    .map(|(_, child)| -precalculated_score(&child).unwrap())
+                    .max()
+                    .unwrap();
+                assert_eq!(Some(best_child), precalculated_score(&game), "{game}");
+            }
+        }
+    }
+
+    /// Compares each precalculated score with a search from scratch. Catches a stale or wrongly
+    /// generated `scores.dat`, but takes hours. Run with `cargo test --release -- --ignored`.
+    #[test]
+    #[ignore = "takes hours"]
+    fn precalculated_scores_agree_with_search() {
+        let mut solver = Solver::new();
+        for stones in 0..NUM_STONES_PRECALCULATED_UP_TO {
+            for game in unique_positions(stones, true) {
+                assert_eq!(
+                    precalculated_score(&game),
+                    Some(solver.score_no_book(&game)),
+                    "{game}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,268 @@
+//! Byte encoding of moves and boards for exchanging them over the network, e.g. between the server
+//! and the clients of an online game. Only available with the `protocol` feature.
+//!
+//! Each message starts with the version of the encoding, [`VERSION`]. Decoding rejects other
+//! versions, messages of the wrong length and contents which do not describe a legal move or a
+//! possible board. Future versions may change the layout, but decoding will keep supporting the
+//! versions released before.
+
+use std::fmt;
+
+use crate::{Column, ConnectFour, Player};
+
+/// Version of the encoding written by this library.
+pub const VERSION: u8 = 1;
+
+/// Length of an encoded [`Move`] in bytes.
+pub const MOVE_LEN: usize = 3;
+
+/// Length of an encoded board in bytes.
+pub const BOARD_LEN: usize = 11;
+
+/// Action of a player. Besides dropping a stone, this covers the variants the board supports.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Move {
+    /// Drops a stone into the column, see [`ConnectFour::play`].
+    Drop(Column),
+    /// Removes the bottom stone of the column, see [`ConnectFour::pop`].
+    Pop(Column),
+    /// Exchanges the roles of the players, see [`ConnectFour::swap_players`].
+    SwapPlayers,
+}
+
+/// Reason why a message could not be decoded.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ProtocolError {
+    /// The message has been encoded with a version of the protocol this library does not know.
+    UnsupportedVersion(u8),
+    /// The message is shorter or longer than messages of its kind.
+    WrongLength { expected: usize, actual: usize },
+    /// The message is not a valid move.
+    InvalidMove,
+    /// The message describes a board which can not be reached by legal play, or the metadata does
+    /// not match the stones.
+    ImpossibleBoard,
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProtocolError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported protocol version {version}.")
+            }
+            ProtocolError::WrongLength { expected, actual } => {
+                write!(f, "Expected a message of {expected} bytes, got {actual}.")
+            }
+            ProtocolError::InvalidMove => write!(f, "Invalid move."),
+            ProtocolError::ImpossibleBoard => write!(f, "Impossible board."),
+        }
+    }
+}
+
+impl std::error::Error for ProtocolError {}
+
+// Kinds of moves, as stored in the second byte of an encoded move.
+const DROP: u8 = 0;
+const POP: u8 = 1;
+const SWAP_PLAYERS: u8 = 2;
+
+/// Encodes `mv` as the version, the kind of move and the index of the column (`0` for
+/// [`Move::SwapPlayers`]).
+pub fn encode_move(mv: Move) -> [u8; MOVE_LEN] {
+    let (kind, column) = match mv {
+        Move::Drop(column) => (DROP, column.0),
+        Move::Pop(column) => (POP, column.0),
+        Move::SwapPlayers => (SWAP_PLAYERS, 0),
+    };
+    [VERSION, kind, column]
+}
+
+/// Inverse of [`encode_move`]. Only checks that the move exists, not whether it is legal for a
+/// particular board.
+pub fn decode_move(bytes: &[u8]) -> Result<Move, ProtocolError> {
+    let [kind, column] = payload(bytes)?;
+    if column >= 7 {
+        return Err(ProtocolError::InvalidMove);
+    }
+    let column = Column::from_index(column);
+    match (kind, column.0) {
+        (DROP, _) => Ok(Move::Drop(column)),
+        (POP, _) => Ok(Move::Pop(column)),
+        (SWAP_PLAYERS, 0) => Ok(Move::SwapPlayers),
+        _ => Err(ProtocolError::InvalidMove),
+    }
+}
+
+// State of the game, as stored in the second byte of an encoded board.
+const PLAYER_ONE_TO_MOVE: u8 = 0;
+const PLAYER_TWO_TO_MOVE: u8 = 1;
+const PLAYER_ONE_WON: u8 = 2;
+const PLAYER_TWO_WON: u8 = 3;
+const DRAW: u8 = 4;
+
+/// Flag in the third byte of an encoded board, set if the players exchanged their roles.
+const PLAYERS_SWAPPED: u8 = 1;
+
+/// Encodes `game` as the version, the state of the game (who is to move, or the result), whether
+/// the players have been swapped and [`ConnectFour::encode`] as eight bytes in little endian. The
+/// state is implied by the stones, but spares the receiver from working it out.
+pub fn encode_board(game: &ConnectFour) -> [u8; BOARD_LEN] {
+    let mut bytes = [0; BOARD_LEN];
+    bytes[0] = VERSION;
+    bytes[1] = state(game);
+    bytes[2] = if game.players_swapped {
+        PLAYERS_SWAPPED
+    } else {
+        0
+    };
+    bytes[3..].copy_from_slice(&game.encode().to_le_bytes());
+    bytes
+}
+
+/// Inverse of [`encode_board`]. Rejects boards which can not be reached by legal play, e.g. if
+/// the players do not have their fair share of stones, both of them have won, the game has been won
+/// before the last stone, or the state does not match the stones. See [`ConnectFour::from_key`].
+/// Boards reached by popping stones (see [`ConnectFour::pop`]) are rejected if they break these
+/// rules, too.
+pub fn decode_board(bytes: &[u8]) -> Result<ConnectFour, ProtocolError> {
+    let payload: [u8; BOARD_LEN - 1] = payload(bytes)?;
+    let (state_byte, flags) = (payload[0], payload[1]);
+    if flags & !PLAYERS_SWAPPED != 0 {
+        return Err(ProtocolError::ImpossibleBoard);
+    }
+    let key = u64::from_le_bytes(payload[2..].try_into().unwrap());
+    let mut game = ConnectFour::from_key(key).map_err(|_| ProtocolError::ImpossibleBoard)?;
+    if flags == PLAYERS_SWAPPED {
+        game.swap_players();
+    }
+    if state(&game) != state_byte {
+        return Err(ProtocolError::ImpossibleBoard);
+    }
+    Ok(game)
+}
+
+/// Bytes following the version, if the length is right and the version is supported.
+fn payload<const N: usize>(bytes: &[u8]) -> Result<[u8; N], ProtocolError> {
+    let (&version, payload) = bytes.split_first().ok_or(ProtocolError::WrongLength {
+        expected: N + 1,
+        actual: 0,
+    })?;
+    if version != VERSION {
+        return Err(ProtocolError::UnsupportedVersion(version));
+    }
+    payload.try_into().map_err(|_| ProtocolError::WrongLength {
+        expected: N + 1,
+        actual: bytes.len(),
+    })
+}
+
+/// Who is to move, or the result if the game is over.
+fn state(game: &ConnectFour) -> u8 {
+    match game.winner() {
+        Some(Player::One) => PLAYER_ONE_WON,
+        Some(Player::Two) => PLAYER_TWO_WON,
+        None if game.is_over() => DRAW,
+        None if game.stones().is_multiple_of(2) != game.players_swapped => PLAYER_ONE_TO_MOVE,
+        None => PLAYER_TWO_TO_MOVE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_moves() {
+        let moves = [
+            Move::Drop(Column::from_index(3)),
+            Move::Pop(Column::from_index(6)),
+            Move::SwapPlayers,
+        ];
+        for mv in moves {
+            assert_eq!(Ok(mv), decode_move(&encode_move(mv)));
+        }
+    }
+
+    #[test]
+    fn reject_malformed_moves() {
+        assert_eq!(
+            Err(ProtocolError::InvalidMove),
+            decode_move(&[VERSION, DROP, 7])
+        );
+        assert_eq!(
+            Err(ProtocolError::InvalidMove),
+            decode_move(&[VERSION, 3, 0])
+        );
+        assert_eq!(
+            Err(ProtocolError::InvalidMove),
+            decode_move(&[VERSION, SWAP_PLAYERS, 1])
+        );
+        assert_eq!(
+            Err(ProtocolError::UnsupportedVersion(2)),
+            decode_move(&[2, DROP, 0])
+        );
+        assert_eq!(
+            Err(ProtocolError::WrongLength {
+                expected: MOVE_LEN,
+                actual: 2
+            }),
+            decode_move(&[VERSION, DROP])
+        );
+    }
+
+    #[test]
+    fn round_trip_boards() {
+        let mut swapped = ConnectFour::from_move_list("4");
+        swapped.swap_players();
+        let boards = [
+            ConnectFour::new(),
+            ConnectFour::from_move_list("4453"),
+            // Won by player one
+            ConnectFour::from_move_list("4455667"),
+            // Draw
+            ConnectFour::from_move_list("547125662261271266215743771576315353334444"),
+            swapped,
+        ];
+        for game in boards {
+            let bytes = encode_board(&game);
+            assert_eq!(
+                Ok(bytes),
+                decode_board(&bytes).map(|game| encode_board(&game))
+            );
+            assert!(decode_board(&bytes).unwrap() == game);
+        }
+    }
+
+    #[test]
+    fn reject_impossible_boards() {
+        let error = |state: u8, flags: u8, key: u64| {
+            let mut bytes = [VERSION, state, flags, 0, 0, 0, 0, 0, 0, 0, 0];
+            bytes[3..].copy_from_slice(&key.to_le_bytes());
+            decode_board(&bytes).err()
+        };
+        let impossible = Some(ProtocolError::ImpossibleBoard);
+        let valid = ConnectFour::from_move_list("44").encode();
+
+        assert_eq!(None, error(PLAYER_ONE_TO_MOVE, 0, valid));
+        // State does not match the stones
+        assert_eq!(impossible, error(PLAYER_TWO_TO_MOVE, 0, valid));
+        // Three stones in the first column, none of them put in by the player who moved last.
+        assert_eq!(impossible, error(PLAYER_ONE_TO_MOVE, 0, 0b111));
+        // Seven stones in a column
+        assert_eq!(impossible, error(PLAYER_TWO_TO_MOVE, 0, 0b111_1111));
+        // Bits beyond the board
+        assert_eq!(impossible, error(PLAYER_ONE_TO_MOVE, 0, 1 << 60));
+        // Player one won with the seventh stone, yet the game went on.
+        let won_before = ConnectFour::from_move_list("121212113").encode();
+        assert_eq!(impossible, error(PLAYER_ONE_WON, 0, won_before));
+        // Unknown flags
+        assert_eq!(impossible, error(PLAYER_ONE_TO_MOVE, 2, valid));
+        assert_eq!(
+            Some(ProtocolError::WrongLength {
+                expected: BOARD_LEN,
+                actual: 1
+            }),
+            decode_board(&[VERSION]).err()
+        );
+    }
+}
@@ -0,0 +1,257 @@
+use std::{fmt, io};
+
+use crate::{Board, Cell, CellMap, Column, ConnectFour};
+
+/// Selects how [`Board::render`] draws the board.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RenderStyle {
+    /// Plain ASCII using `X` and `O`. Same as [`Board::print_to`].
+    Ascii,
+    /// Unicode box drawing characters, with filled circles for player one and hollow circles for
+    /// player two.
+    Unicode,
+    /// Like [`RenderStyle::Unicode`], but with red filled circles for player one and yellow filled
+    /// circles for player two, using ANSI escape codes. Requires a terminal supporting colors.
+    AnsiColor,
+}
+
+impl<const W: usize, const H: usize> Board<W, H> {
+    /// Prints out a text representation of a board to `out`, in the given style.
+    pub fn render(&self, style: RenderStyle, out: impl io::Write) -> io::Result<()> {
+        match style {
+            RenderStyle::Ascii => self.print_with_symbols('X', 'O', ' ', out),
+            RenderStyle::Unicode => self.render_box_drawing("●", "○", out),
+            RenderStyle::AnsiColor => {
+                self.render_box_drawing("\x1b[31m●\x1b[0m", "\x1b[33m●\x1b[0m", out)
+            }
+        }
+    }
+
+    /// Same as [`Self::render`], followed by a row with a `*` below each of the `marked` columns,
+    /// e.g. to point out the best moves.
+    pub fn render_marked(
+        &self,
+        style: RenderStyle,
+        marked: &[Column],
+        mut out: impl io::Write,
+    ) -> io::Result<()> {
+        self.render(style, &mut out)?;
+        let marks: String = (0..W as u8)
+            .map(|column| {
+                if marked.contains(&Column::from_index(column)) {
+                    " *"
+                } else {
+                    "  "
+                }
+            })
+            .collect();
+        writeln!(out, "{}", marks.trim_end())
+    }
+
+    /// Prints out a text representation of a board to `out`, using `one` for stones of player one,
+    /// `two` for stones of player two and `empty` for empty cells. Symbols which take up two
+    /// columns in a terminal (e.g. most emoji) are supported. Narrower symbols are padded with
+    /// spaces so all cells have the same width.
+    pub fn print_with_symbols(
+        &self,
+        one: char,
+        two: char,
+        empty: char,
+        mut out: impl io::Write,
+    ) -> io::Result<()> {
+        let board = WithSymbols {
+            board: self,
+            one,
+            two,
+            empty,
+        };
+        write!(out, "{board}")
+    }
+
+    /// Self-contained SVG image of the board. Stones of player one are red, stones of player two
+    /// yellow, empty cells are white holes in a blue board. Each cell is 100 pixels wide and high.
+    pub fn to_svg(&self) -> String {
+        self.to_svg_highlighted(&[])
+    }
+
+    /// Same as [`Self::to_svg`], but draws a ring around each of the cells in `highlight`. Cells
+    /// are identified by `(row, column)`, with the bottom row and the leftmost column being `0`.
+    pub fn to_svg_highlighted(&self, highlight: &[(u8, u8)]) -> String {
+        let (width, height) = (W * 100, H * 100);
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+            viewBox=\"0 0 {width} {height}\">\n\
+            <rect width=\"{width}\" height=\"{height}\" fill=\"#1f4fbf\"/>\n",
+        );
+        for row in 0..H as u8 {
+            for column in 0..W as u8 {
+                let fill = match self.cell(row, column) {
+                    Cell::PlayerOne => "#e02020",
+                    Cell::PlayerTwo => "#f0d020",
+                    Cell::Empty => "#ffffff",
+                };
+                let stroke = if highlight.contains(&(row, column)) {
+                    " stroke=\"#20e020\" stroke-width=\"8\""
+                } else {
+                    ""
+                };
+                // Row 0 is at the bottom of the image.
+                let cx = column as u32 * 100 + 50;
+                let cy = (H as u32 - 1 - row as u32) * 100 + 50;
+                svg.push_str(&format!(
+                    "<circle cx=\"{cx}\" cy=\"{cy}\" r=\"40\" fill=\"{fill}\"{stroke}/>\n"
+                ));
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    fn render_box_drawing(&self, one: &str, two: &str, mut out: impl io::Write) -> io::Result<()> {
+        for row in (0..H as u8).rev() {
+            for field in (0..W as u8).map(|column| self.cell(row, column)) {
+                let c = match field {
+                    Cell::PlayerOne => one,
+                    Cell::PlayerTwo => two,
+                    Cell::Empty => " ",
+                };
+                write!(out, "│{c}")?;
+            }
+            writeln!(out, "│")?;
+        }
+        let bottom = vec!["─"; W].join("┴");
+        let labels: String = (1..=W).map(|column| format!(" {column}")).collect();
+        writeln!(out, "└{bottom}┘\n{labels}")
+    }
+}
+
+/// Displays a board like [`Board::print_with_symbols`] prints it. Implementing [`fmt::Display`]
+/// allows to write it to an [`io::Write`] as well as to a [`fmt::Formatter`] without a buffer.
+pub(crate) struct WithSymbols<'a, const W: usize, const H: usize> {
+    pub board: &'a Board<W, H>,
+    pub one: char,
+    pub two: char,
+    pub empty: char,
+}
+
+impl<const W: usize, const H: usize> fmt::Display for WithSymbols<'_, W, H> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Self {
+            board,
+            one,
+            two,
+            empty,
+        } = *self;
+        let width = [one, two, empty]
+            .map(display_width)
+            .into_iter()
+            .max()
+            .unwrap();
+        for row in (0..H as u8).rev() {
+            for field in (0..W as u8).map(|column| board.cell(row, column)) {
+                let c = match field {
+                    Cell::PlayerOne => one,
+                    Cell::PlayerTwo => two,
+                    Cell::Empty => empty,
+                };
+                let padding = width - display_width(c);
+                write!(f, "|{c}{:padding$}", "")?;
+            }
+            writeln!(f, "|")?;
+        }
+        writeln!(f, "{:-<1$}", "", W * (width + 1) + 1)?;
+        for column in 1..W {
+            write!(f, " {column:<width$}")?;
+        }
+        // No padding after the last label
+        writeln!(f, " {W}")
+    }
+}
+
+impl ConnectFour {
+    /// Prints out the board like [`Board::print_to`], but shows the value `values` holds for each
+    /// empty cell, instead of leaving it blank. Cells holding a stone show `X` or `O`. All cells are
+    /// padded with spaces to the width of the widest value, so the columns line up.
+    pub fn render_cell_map<T: fmt::Display>(
+        &self,
+        values: &CellMap<T>,
+        out: impl io::Write,
+    ) -> io::Result<()> {
+        let texts = CellMap::from_fn(|row, column| match self.cell(row, column) {
+            Cell::PlayerOne => "X".to_owned(),
+            Cell::PlayerTwo => "O".to_owned(),
+            Cell::Empty => values[(row, column)].to_string(),
+        });
+        let width = texts
+            .0
+            .iter()
+            .flatten()
+            .map(|text| text_width(text))
+            .max()
+            .unwrap();
+        print_cell_texts(&texts, width, out)
+    }
+
+    /// Same as [`Board::print_to`], but each cell and column label is padded with spaces to `width`,
+    /// so it lines up with a row of values of that width printed below.
+    pub(crate) fn print_with_cell_width(
+        &self,
+        width: usize,
+        out: impl io::Write,
+    ) -> io::Result<()> {
+        let texts = CellMap::from_fn(|row, column| match self.cell(row, column) {
+            Cell::PlayerOne => "X",
+            Cell::PlayerTwo => "O",
+            Cell::Empty => "",
+        });
+        print_cell_texts(&texts, width, out)
+    }
+}
+
+/// Prints one text per cell, each padded with spaces to `width`, followed by the column labels.
+fn print_cell_texts<T: AsRef<str>>(
+    texts: &CellMap<T>,
+    width: usize,
+    mut out: impl io::Write,
+) -> io::Result<()> {
+    for row in (0..6).rev() {
+        for column in 0..7 {
+            let text = texts[(row, column)].as_ref();
+            let padding = width - text_width(text);
+            write!(out, "|{text}{:padding$}", "")?;
+        }
+        writeln!(out, "|")?;
+    }
+    let bottom = "-".repeat(7 * (width + 1) + 1);
+    let labels: String = (1..=7).map(|column| format!(" {column:<width$}")).collect();
+    writeln!(out, "{bottom}\n{}", labels.trim_end())
+}
+
+/// Number of columns `text` takes up in a terminal.
+fn text_width(text: &str) -> usize {
+    text.chars().map(display_width).sum()
+}
+
+/// Number of columns `c` takes up in a terminal. Two for wide characters like CJK or emoji, one
+/// otherwise. Covers the common ranges of wide characters, not all of them.
+fn display_width(c: char) -> usize {
+    match c as u32 {
+        0x1100..=0x115F
+        | 0x2E80..=0x303E
+        | 0x3041..=0x33FF
+        | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF
+        | 0xA000..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x1F300..=0x1F64F
+        | 0x1F680..=0x1F6FF
+        | 0x1F7E0..=0x1F7EB
+        | 0x1F900..=0x1F9FF
+        | 0x20000..=0x3FFFD => 2,
+        _ => 1,
+    }
+}
@@ -0,0 +1,48 @@
+/// Source of random numbers for the randomized parts of the solver, e.g.
+/// [`crate::Solver::suggest_move`]. Passing it in keeps these reproducible: The same sequence of
+/// numbers yields the same moves.
+///
+/// [`XorShift`] is a seedable implementation. Wrapping the generator of another crate takes a
+/// single method.
+pub trait Rng {
+    /// Next number of the sequence. All bits are expected to be evenly distributed.
+    fn next_u64(&mut self) -> u64;
+}
+
+/// Small and fast pseudo random number generator. Good enough to pick moves, but not meant for
+/// anything security related.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct XorShift(u64);
+
+impl XorShift {
+    /// Generator yielding the same sequence for the same `seed`.
+    pub fn new(seed: u64) -> Self {
+        // A state of zero would only ever yield zeros.
+        XorShift(if seed == 0 { 0x2545_f491_4f6c_dd1d } else { seed })
+    }
+}
+
+impl Rng for XorShift {
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+/// Puts `items` into an order picked uniformly at random (Fisher-Yates).
+pub(crate) fn shuffle<T>(rng: &mut impl Rng, items: &mut [T]) {
+    for last in (1..items.len()).rev() {
+        let other = (rng.next_u64() % (last as u64 + 1)) as usize;
+        items.swap(last, other);
+    }
+}
+
+/// One of the `items` picked uniformly at random. `None` if `items` is empty.
+pub(crate) fn choose<T: Copy>(rng: &mut impl Rng, items: &[T]) -> Option<T> {
+    if items.is_empty() {
+        return None;
+    }
+    Some(items[(rng.next_u64() % items.len() as u64) as usize])
+}
@@ -1,252 +1,1121 @@
-use std::cmp::{max, min, Ordering};
-
-use crate::{
-    precalculated::precalculated_score, transposition_table::TranspositionTable, Column, ConnectFour
-};
-
-/// Reusing the same solver instead of repeatedly running score in order to calculate similar
-/// positions, may have performance benefits, because we can reuse the transposition table.
-pub struct Solver {
-    transposition_table: TranspositionTable,
-}
-
-impl Default for Solver {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-impl Solver {
-    pub fn new() -> Solver {
-        // 64Bit per entry. Let's hardcode it to use a prime close to 16777213 which multiplied by 8
-        // Byte should be close to 128MiB.
-        let transposition_table = TranspositionTable::new(16777213);
-        Solver {
-            transposition_table,
-        }
-    }
-
-    /// Calculates the score of a connect four game. The score is set up so always picking the move with
-    /// the lowest score results in perfect play. Perfect meaning winning as fast as possible, drawing
-    /// or loosing as late as possible.
-    ///
-    /// A positive score means the player who can put in the next stone can win. Positions which can be
-    /// won faster are scored higher. The score is 1 if the current player can win with his last stone.
-    /// Two if he can win with his second to last stone and so on. A score of zero means the game will
-    /// end in a draw if both players play perfectly. A negative score means the opponent (the player
-    /// which is not putting in the next stone) is winnig. It is `-1` if the opponent is winning with
-    /// his last stone. `-2` if he is winning second to last stone and so on.
-    pub fn score(&mut self, game: &ConnectFour) -> i8 {
-        precalculated_score(game)
-            .unwrap_or_else(|| self.score_without_precalculated(game))
-    }
-
-    fn score_without_precalculated(&mut self, game: &ConnectFour) -> i8 {
-        if game.is_victory() {
-            return score_from_num_stones(game.stones() as i8);
-        }
-
-        // Check if we can win in the next move because `alpha_beta` assumes that the next move can not
-        // win the game.
-        if game.can_win_in_next_move() {
-            return -score_from_num_stones(game.stones() as i8 + 1);
-        }
-
-        // 64Bit per entry. Let's hardcode it to use a prime close to 16777213 which multiplied by 8
-        // Byte should be close to 128MiB.
-        let mut min = -(42 - game.stones() as i8) / 2;
-        let mut max = (42 + 1 - game.stones() as i8) / 2;
-
-        // Iterative deepening
-        while min < max {
-            let median = min + (max - min) / 2;
-            let alpha = if median <= 0 && min / 2 < median {
-                // Explore loosing path deeper
-                min / 2
-            } else if median >= 0 && max / 2 > median {
-                // Explore winning path deeper
-                max / 2
-            } else {
-                median
-            };
-            let result = alpha_beta(game, alpha, alpha + 1, &mut self.transposition_table);
-            if result <= alpha {
-                max = result;
-            } else {
-                min = result;
-            }
-        }
-        debug_assert_eq!(min, max);
-        min
-    }
-
-    /// Fills `best_moves` with all the legal moves, which have the best strong score.
-    pub fn best_moves(&mut self, game: &ConnectFour, best_moves: &mut Vec<Column>) {
-        if game.is_over() {
-            return;
-        }
-        let mut min = i8::MAX;
-        for column in game.legal_moves() {
-            let mut board = *game;
-            board.play(column);
-            let score = self.score(&board);
-            match score.cmp(&min) {
-                Ordering::Less => {
-                    min = score; 
-                    best_moves.clear();
-                    best_moves.push(column);
-                },
-                Ordering::Equal => {
-                    best_moves.push(column);
-                },
-                Ordering::Greater => (),
-            };
-        }
-    }
-}
-
-/// Calculates the score of a connect four game. The score is set up so always picking the move with
-/// the lowest score results in perfect play. Perfect meaning winning as fast as possible, drawing
-/// or loosing as late as possible.
-///
-/// A positive score means the player who can put in the next stone can win. Positions which can be
-/// won faster are scored higher. The score is 1 if the current player can win with his last stone.
-/// Two if he can win with his second to last stone and so on. A score of zero means the game will
-/// end in a draw if both players play perfectly. A negative score means the opponent (the player
-/// which is not putting in the next stone) is winnig. It is `-1` if the opponent is winning with
-/// his last stone. `-2` if he is winning second to last stone and so on.
-pub fn score(game: &ConnectFour) -> i8 {
-    Solver::new().score(game)
-}
-
-/// Score of the position with alepha beta pruning.
-///
-/// Assumes that position can not be won in a single move. Assumes that position is not won position
-/// already.
-///
-/// * If actual score is smaller than alpha then: actual score <= return value <= alpha
-/// * If actual score is bigger than beta then: actual score >= return value >= beta
-/// * If score is within alpha beta window precise score is returned
-///
-/// If alpha is higher (or equal) than the score of this position, we can prune this position,
-/// because the current player would not play this route, since he is guaranteed to achieve a better
-/// outcome with some other play.
-///
-/// Similarly if this positions score is higher than beta we can prune it, since the opponent would
-/// choose a different line of play, which leavs him in a better position.
-///
-/// Alpha is a lower bound on what the current player can expect. Beta is as upper bound on what he
-/// can expect.
-fn alpha_beta(
-    game: &ConnectFour,
-    mut alpha: i8,
-    mut beta: i8,
-    cached_beta: &mut TranspositionTable,
-) -> i8 {
-    debug_assert!(alpha < beta);
-    debug_assert!(!game.can_win_in_next_move());
-
-    let possibilities = game.non_loosing_moves();
-    if possibilities.is_empty() {
-        // If there are no possibilities for the current player not to loose, the opponent wins.
-        return score_from_num_stones(game.stones() as i8 + 2);
-    }
-
-    // Check for draw
-    if game.stones() >= 42 - 2 {
-        return 0;
-    }
-
-    // Opponent can not win within one move, this gives us a lower bound for the score
-    alpha = max(alpha, score_from_num_stones(game.stones() as i8 + 4));
-    if alpha >= beta {
-        return alpha;
-    }
-
-    // We may also find an upper bound in the cache. If not we use the fact that we know we can not
-    // win with our next stone, which puts the fastest possible win at least three stones away.
-    let upper_bound_beta = cached_beta
-        .get(game.encode())
-        .unwrap_or_else(|| -score_from_num_stones(game.stones() as i8 + 3));
-    beta = min(beta, upper_bound_beta);
-    if alpha >= beta {
-        return beta;
-    }
-
-    let mut move_explorer = MoveExplorer::new();
-    for col in 0..7 {
-        if possibilities.contains(col) {
-            move_explorer.add(col, game);
-        }
-    }
-    move_explorer.sort();
-
-    // We play the position which is the worst for our opponent
-    for position in move_explorer.next_positions() {
-        // Score from the perspective of the current player is the negative of the opponents.
-        let score = -alpha_beta(&position, -beta, -alpha, cached_beta);
-        // prune the exploration if we find a possible move better than what we were looking for.
-        if score >= beta {
-            return score;
-        }
-        // We only need to search for positions, which are better than the best so far.
-        alpha = max(alpha, score);
-    }
-
-    // save the upper bound of the position
-    cached_beta.put(game.encode(), alpha);
-    alpha
-}
-
-/// Score from the perspective of the current player (who can no longer move, because the game is
-/// over), assuming the last stone won after `num_stones`.
-fn score_from_num_stones(num_stones: i8) -> i8 {
-    // Remaining stones of the winning player.
-    let remaining_stones = (42 - num_stones) / 2;
-    // Score is from the perspective of the moving player. So if the current position is a win, it
-    // is negative.
-    -(remaining_stones + 1)
-}
-
-/// Stack allocated container for possible moves. Iterates over moves in a fashion which allows to
-/// prune the search tree sooner.
-struct MoveExplorer {
-    /// Up to seven indices are possible. Store index, score and position.
-    col_indices: [(u8, u32, ConnectFour); 7],
-    /// Up to this index the moves are valid.
-    len: usize,
-}
-
-impl MoveExplorer {
-    pub fn new() -> Self {
-        Self {
-            col_indices: [(0, 0, ConnectFour::new()); 7],
-            len: 0,
-        }
-    }
-
-    pub fn add(&mut self, col_index: u8, from: &ConnectFour) {
-        let mut next_position = *from;
-        let is_legal = next_position.play(Column::from_index(col_index));
-        debug_assert!(is_legal);
-        let score = next_position.heuristic();
-        self.col_indices[self.len] = (col_index, score, next_position);
-        self.len += 1;
-    }
-
-    pub fn sort(&mut self) {
-        /// Indices which should get explored first get smaller values. Explore center moves first.
-        /// These are better on average. This allows for faster pruning.
-        const COLUMN_PRIORITY: [u8; 7] = [6, 4, 2, 0, 1, 3, 5];
-        self.col_indices[..self.len].sort_unstable_by(|a, b| {
-            // sort by score first, then by column priority. We prefer higher scores, therfore a, b
-            // are switched in order.
-            b.1.cmp(&a.1)
-                .then_with(|| COLUMN_PRIORITY[a.0 as usize].cmp(&COLUMN_PRIORITY[b.0 as usize]))
-        });
-    }
-
-    pub fn next_positions(&self) -> impl Iterator<Item = ConnectFour> + '_ {
-        self.col_indices[..self.len].iter().map(|(_, _, pos)| *pos)
-    }
-}
+use std::{
+    cmp::{max, min, Ordering},
+    fs::File,
+    io::{self, BufReader, BufWriter, Write},
+    path::Path,
+    sync::atomic::{self, AtomicBool},
+};
+
+#[cfg(feature = "rayon")]
+use crate::transposition_table::ConcurrentTranspositionTable;
+use crate::{
+    bitboard::every_other_row,
+    precalculated::precalculated_score,
+    rng::{choose, shuffle, Rng, XorShift},
+    transposition_table::{ScoreCache, TranspositionTable},
+    Column, ConnectFour, Player, TOTAL_CELLS,
+};
+
+/// Number of buckets in the transposition table of [`Solver::new`]. Two entries with 8 Byte each
+/// per bucket. Let's hardcode it to use a prime close to 2^22, which multiplied by 16 Byte should be
+/// close to 64MiB.
+const DEFAULT_BUCKETS: usize = 4194301;
+
+/// Reusing the same solver instead of repeatedly running score in order to calculate similar
+/// positions, may have performance benefits, because we can reuse the transposition table.
+pub struct Solver {
+    transposition_table: TranspositionTable,
+    /// Number of positions explored by `alpha_beta` so far.
+    nodes: u64,
+    /// `false` if the precalculated scores are ignored, see [`Solver::without_book`].
+    use_book: bool,
+    /// Picks among equally good moves in [`Solver::best_move`]. `None` picks the first one, as
+    /// ordered by `tie_break`.
+    rng: Option<XorShift>,
+    /// Order of equally good moves in [`Solver::best_move`], unless `rng` is set.
+    tie_break: TieBreak,
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Solver {
+    pub fn new() -> Solver {
+        Self::with_capacity(DEFAULT_BUCKETS)
+    }
+
+    /// Creates a solver whose transposition table has `capacity` buckets. Each bucket takes 16
+    /// Byte. Smaller tables save memory, but make the search slower.
+    ///
+    /// # Panics
+    ///
+    /// `capacity` must not be zero. Any other value is correct, but primes are a good choice, since
+    /// they spread the positions evenly over the buckets.
+    pub fn with_capacity(capacity: usize) -> Solver {
+        let transposition_table = TranspositionTable::new(capacity);
+        Solver {
+            transposition_table,
+            nodes: 0,
+            use_book: true,
+            rng: None,
+            tie_break: TieBreak::Ascending,
+        }
+    }
+
+    /// Creates a solver which never looks up the precalculated scores of positions with few stones
+    /// (see [`crate::precalculated_depth`]), but searches them like any other position. Useful for
+    /// debugging and to measure the time the search takes in the worst case. Each of the methods
+    /// behaves like [`Self::score_no_book`] then. Positions with few stones may take minutes to
+    /// score, rather than no time at all.
+    pub fn without_book() -> Solver {
+        Solver {
+            use_book: false,
+            ..Self::new()
+        }
+    }
+
+    /// Creates a solver whose [`Self::best_move`] picks randomly among equally good moves, using a
+    /// generator seeded with `seed`. The generator keeps its state from one call to the next, so
+    /// the seed determines every move of a whole game: Asking for the best moves of the same
+    /// positions in the same order yields the same moves. Useful for tournaments between bots,
+    /// which should vary their play, yet be possible to replay.
+    pub fn seeded(seed: u64) -> Solver {
+        Solver {
+            rng: Some(XorShift::new(seed)),
+            ..Self::new()
+        }
+    }
+
+    /// Calculates the score of a connect four game. The score is set up so always picking the move with
+    /// the lowest score results in perfect play. Perfect meaning winning as fast as possible, drawing
+    /// or loosing as late as possible.
+    ///
+    /// A positive score means the player who can put in the next stone can win. Positions which can be
+    /// won faster are scored higher. The score is 1 if the current player can win with his last stone.
+    /// Two if he can win with his second to last stone and so on. A score of zero means the game will
+    /// end in a draw if both players play perfectly. A negative score means the opponent (the player
+    /// which is not putting in the next stone) is winnig. It is `-1` if the opponent is winning with
+    /// his last stone. `-2` if he is winning second to last stone and so on.
+    pub fn score(&mut self, game: &ConnectFour) -> i8 {
+        self.book_score(game)
+            .unwrap_or_else(|| self.score_no_book(game))
+    }
+
+    /// Same as [`Self::score`], but uses MTD(f) rather than bisection in order to narrow down the
+    /// score with null window searches. Starting with a guess of `0` (draw), each search moves the
+    /// guess towards the true score. Both drivers always yield the same score, but may differ in
+    /// the number of positions they need to explore.
+    pub fn score_mtdf(&mut self, game: &ConnectFour) -> i8 {
+        if let Some(score) = self.book_score(game).or_else(|| immediate_score(game)) {
+            return score;
+        }
+        self.transposition_table.set_root_stones(game.stones());
+
+        let (mut lower, mut upper) = game.score_bounds();
+        let mut guess = 0;
+        while lower < upper {
+            let beta = max(guess, lower + 1);
+            guess = alpha_beta(
+                game,
+                game.openings(),
+                beta - 1,
+                beta,
+                &mut self.transposition_table,
+                &mut self.nodes,
+                &NEVER_CANCELLED,
+            )
+            .expect("Search is never cancelled");
+            if guess < beta {
+                upper = guess;
+            } else {
+                lower = guess;
+            }
+        }
+        debug_assert_eq!(lower, upper);
+        lower
+    }
+
+    /// Registers known scores of positions with the solver, before searching. Each entry consists
+    /// of the key of a position, as returned by [`ConnectFour::encode`], and its score, as returned
+    /// by [`Self::score`]. Useful to speed up searches which keep running into the same slow
+    /// positions, without regenerating the precalculated scores.
+    ///
+    /// The scores are put into the transposition table, and just like the results of the search
+    /// itself they get evicted by other positions over time. Positions with few stones are kept
+    /// longest, until the game moves past them. A wrong score leads to wrong results.
+    pub fn preload(&mut self, entries: &[(u64, i8)]) {
+        for &(key, score) in entries {
+            let key = self.transposition_table.key_of_encoded(key);
+            self.transposition_table.put(key, score, stones_of_key(key));
+        }
+    }
+
+    /// What the transposition table knows about `game`, without searching. The search stores upper
+    /// bounds: The actual score of `game` is at most the returned value, but may be lower. `None` if
+    /// the position is not in the table, e.g. because it has not been searched yet or has been
+    /// evicted since. Positions answered by the precalculated scores are never in the table.
+    pub fn cached_bound(&self, game: &ConnectFour) -> Option<i8> {
+        self.transposition_table
+            .get(self.transposition_table.key_of(game))
+    }
+
+    /// Winner of `game` predicted from the parity of the rows alone, without searching. `None` if
+    /// the prediction does not apply, which is the case for most positions.
+    ///
+    /// Applies Allis' "claimeven" rule: If every column holds an even number of stones, the player
+    /// who moved last can answer each move by playing on top of it. Each cell in the first, third
+    /// and fifth row goes to the player to move then, each cell in the second, fourth and sixth row
+    /// to the other one. If these cells complete a four in a row for the player who moved last, but
+    /// not for the player to move, the player who moved last wins, no matter how the player to move
+    /// plays. Often they can win faster, so the score is not predicted.
+    pub fn parity_outcome(&self, game: &ConnectFour) -> Option<Player> {
+        if game.is_over() || game.heights().iter().any(|height| height % 2 == 1) {
+            return None;
+        }
+        let mut current = game.last;
+        current.flip(game.both);
+        let empty = game.both.empty();
+        let odd_rows = every_other_row::<7, 6>(0);
+        if current.with_stones(empty & odd_rows).is_win()
+            || !game.last.with_stones(empty & !odd_rows).is_win()
+        {
+            return None;
+        }
+        // The number of stones is even.
+        Some(if game.players_swapped {
+            Player::One
+        } else {
+            Player::Two
+        })
+    }
+
+    /// Same as [`Self::score`], but also tells where the score came from. Useful to judge how
+    /// effective the transposition table is for a sequence of queries.
+    pub fn score_source(&mut self, game: &ConnectFour) -> (i8, ScoreSource) {
+        if let Some(score) = self.book_score(game) {
+            return (score, ScoreSource::Book);
+        }
+        let bound = immediate_score(game)
+            .is_none()
+            .then(|| self.cached_bound(game))
+            .flatten();
+        let Some(bound) = bound else {
+            return (self.score_no_book(game), ScoreSource::Searched);
+        };
+        // The table only holds upper bounds. Yet, the score can not be lower than losing to the next
+        // stone of the opponent, or to their second one if they can not win with the next.
+        let lowest = if game.non_loosing_moves().is_empty() {
+            score_from_num_stones(game.stones() as i8 + 2)
+        } else {
+            score_from_num_stones(game.stones() as i8 + 4)
+        };
+        if bound <= lowest {
+            return (bound, ScoreSource::TableExact);
+        }
+        (self.score_no_book(game), ScoreSource::TableBound)
+    }
+
+    /// If `true`, a position and its mirror image share their entry in the transposition table, so
+    /// searching one of them benefits from having searched the other before. Looking up a position
+    /// takes a bit longer, since its mirror image needs to be encoded, too. `false` by default.
+    ///
+    /// Mirrored positions have the same score, so entries stay valid if this is changed later, or
+    /// if a table is loaded with [`Self::load_table`], which does not remember the setting.
+    pub fn set_canonical_keys(&mut self, canonical_keys: bool) {
+        self.transposition_table.set_canonical_keys(canonical_keys);
+    }
+
+    /// Writes the transposition table to a file at `path`, so the positions learned while
+    /// searching can be reused by another process with [`Self::load_table`]. Empty slots are
+    /// skipped, so the file is at most as large as the table.
+    pub fn save_table(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        self.transposition_table.save(&mut out)?;
+        out.flush()
+    }
+
+    /// Creates a solver with the transposition table saved by [`Self::save_table`]. The table has
+    /// the same capacity as the saved one. Scores in the table are bounds, which are valid no matter
+    /// which search found them, so loading them yields the same scores as searching from scratch.
+    pub fn load_table(path: impl AsRef<Path>) -> io::Result<Solver> {
+        let transposition_table = TranspositionTable::load(BufReader::new(File::open(path)?))?;
+        Ok(Solver {
+            transposition_table,
+            nodes: 0,
+            use_book: true,
+            rng: None,
+            tie_break: TieBreak::Ascending,
+        })
+    }
+
+    /// Total number of positions explored by the search since this solver has been created.
+    /// Positions answered by the precalculated scores are not counted.
+    pub fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    /// Same as [`Self::score`], but stops searching and returns `None` soon after `cancel` is set to
+    /// `true`, e.g. by another thread. Positions scored before are kept in the transposition table,
+    /// so scoring the same position again later does not start from scratch.
+    pub fn score_cancellable(&mut self, game: &ConnectFour, cancel: &AtomicBool) -> Option<i8> {
+        if let Some(score) = self.book_score(game) {
+            return Some(score);
+        }
+        self.search(game, cancel)
+    }
+
+    /// Precalculated score of `game`, unless it is unknown or the solver does not use them.
+    fn book_score(&self, game: &ConnectFour) -> Option<i8> {
+        self.use_book.then(|| precalculated_score(game)).flatten()
+    }
+
+    /// Score of `game`, looking at most `depth` stones ahead. Outcomes decided within `depth`
+    /// stones, counting the next one, are scored like [`Self::score`] does. Every position which is
+    /// still open after `depth` stones counts as a draw. So `0` may hide a win or loss further down
+    /// the line.
+    ///
+    /// Neither the precalculated scores nor the transposition table are used, so this plays weaker
+    /// than [`Self::score`], but takes predictable time for small depths, regardless of the
+    /// position.
+    pub fn score_limited(&mut self, game: &ConnectFour, depth: u8) -> i8 {
+        depth_limited(game, depth, -i8::MAX, i8::MAX, &mut self.nodes)
+    }
+
+    /// Same as [`Self::score`], but always searches, rather than looking up the precalculated scores
+    /// of positions with few stones (see [`crate::precalculated_depth`]). Useful to benchmark the
+    /// search, or to verify the precalculated scores. Searching positions with few stones may take
+    /// minutes.
+    pub fn score_no_book(&mut self, game: &ConnectFour) -> i8 {
+        self.search(game, &NEVER_CANCELLED)
+            .expect("Search is never cancelled")
+    }
+
+    /// Number of positions the search explores to score `game`, as a measure of how hard the
+    /// position is to solve, e.g. to sort puzzles by difficulty. Positions whose outcome takes many
+    /// positions to prove tend to be less obvious to human players, too.
+    ///
+    /// For the numbers to be comparable between positions, the transposition table is cleared
+    /// before the search, and the precalculated scores are not used (see [`Self::score_no_book`]).
+    /// Positions with few stones may take minutes. The explored positions are added to
+    /// [`Self::nodes`], too.
+    pub fn search_effort(&mut self, game: &ConnectFour) -> u64 {
+        self.transposition_table.clear();
+        let nodes_before = self.nodes;
+        self.score_no_book(game);
+        self.nodes - nodes_before
+    }
+
+    /// Score of `game` without looking at the precalculated scores. `None` if cancelled.
+    fn search(&mut self, game: &ConnectFour, cancel: &AtomicBool) -> Option<i8> {
+        search(
+            game,
+            &mut self.transposition_table,
+            &mut self.nodes,
+            cancel,
+        )
+    }
+
+    /// Scores `game` and finds the moves both players make from here on with perfect play.
+    ///
+    /// Rather than scoring every move along the way from scratch, only the first legal move (from
+    /// left to right) which keeps the score of the position is confirmed with a null window search.
+    /// These searches are mostly answered by the transposition table, which has been filled while
+    /// scoring `game`.
+    pub fn solve(&mut self, game: &ConnectFour) -> Solution {
+        let score = self.score(game);
+        let mut principal_variation = Vec::new();
+        let mut position = *game;
+        // Score of `position`
+        let mut position_score = score;
+        while !position.is_over() {
+            // The score of every child is at least the negative score of the position, so the best
+            // moves are the ones for which it is also at most that.
+            let (column, _) = position
+                .children()
+                .find(|(_, child)| self.score_at_most(child, -position_score))
+                .expect("One of the moves must lead to the score of the position");
+            position.play(column);
+            position_score = -position_score;
+            principal_variation.push(column);
+        }
+        Solution {
+            score,
+            best_move: principal_variation.first().copied(),
+            principal_variation,
+        }
+    }
+
+    /// Moves of both players until the player to move wins, if they can force a win. Their moves
+    /// win as fast as possible and the replies of their opponent delay the defeat as long as
+    /// possible, so the line is the shortest win which can be forced against any defense. `None` if
+    /// the position is drawn or lost for the player to move.
+    ///
+    /// This is the principal variation of [`Self::solve`]. A move keeping the exact score wins in
+    /// the least number of stones or, for the opponent, postpones the defeat the most.
+    pub fn forced_win_line(&mut self, game: &ConnectFour) -> Option<Vec<Column>> {
+        let solution = self.solve(game);
+        (solution.score > 0).then_some(solution.principal_variation)
+    }
+
+    /// `true` if the game ends in a draw with perfect play from here on, i.e. if the
+    /// [score](Self::score) of `game` is zero. Faster than calculating the score, since it only
+    /// needs to rule out a win for either player, not find out how fast it is. Unlike
+    /// [`ConnectFour::is_draw`] the board does not need to be full.
+    pub fn is_forced_draw(&mut self, game: &ConnectFour) -> bool {
+        self.score_at_most(game, 0) && !self.score_at_most(game, -1)
+    }
+
+    /// Whether the current player wins, draws or loses with perfect play from here on, i.e. the
+    /// sign of the [score](Self::score) of `game`. Faster than calculating the score, since the
+    /// search only tells wins, draws and losses apart, not how fast the game is won or lost. Use
+    /// [`Self::score`] or [`Self::plies_to_end`] for the distance to the end of the game.
+    pub fn status(&mut self, game: &ConnectFour) -> GameStatus {
+        if !self.score_at_most(game, 0) {
+            GameStatus::CurrentPlayerWins
+        } else if self.score_at_most(game, -1) {
+            GameStatus::CurrentPlayerLoses
+        } else {
+            GameStatus::Drawn
+        }
+    }
+
+    /// `true` if the score of `game` is lower or equal to `score`.
+    fn score_at_most(&mut self, game: &ConnectFour, score: i8) -> bool {
+        if let Some(actual) = self.book_score(game).or_else(|| immediate_score(game)) {
+            return actual <= score;
+        }
+        let result = alpha_beta(
+            game,
+            game.openings(),
+            score,
+            score + 1,
+            &mut self.transposition_table,
+            &mut self.nodes,
+            &NEVER_CANCELLED,
+        )
+        .expect("Search is never cancelled");
+        result <= score
+    }
+
+    /// Number of stones put into the board until the game ends with perfect play, no matter who
+    /// wins. [`ConnectFour::remaining_moves`] for a draw, `0` if the game is already over. Same as
+    /// the second part of [`interpret_score`] for the score of `game`.
+    pub fn plies_to_end(&mut self, game: &ConnectFour) -> u8 {
+        let (_outcome, stones_to_end) = interpret_score(game, self.score(game));
+        stones_to_end
+    }
+
+    /// Scores every move of the current player. The score at index `i` belongs to the column with
+    /// index `i`, and is `None` if the move is not legal. Scores are from the perspective of the
+    /// current player, i.e. the negative score of the board after the move has been played.
+    /// Higher is better. All scores are `None` if the game is over.
+    pub fn analyze(&mut self, game: &ConnectFour) -> [Option<i8>; 7] {
+        let mut scores = [None; 7];
+        if game.is_over() {
+            return scores;
+        }
+        for (column, child) in game.children() {
+            scores[column.0 as usize] = Some(-self.score(&child));
+        }
+        scores
+    }
+
+    /// For each legal move of the current player, the score of the best reply of the opponent, from
+    /// the perspective of the opponent. Higher is better for the opponent, so a negative score means
+    /// the move wins. Shows what the opponent is up to, without playing a move.
+    ///
+    /// The best reply scores the same as the board after the move, so each score is the negative
+    /// of the score [`Self::analyze`] reports for the move. For moves which win right away, there is
+    /// no reply and the score is the one of the lost game. Empty if the game is over.
+    pub fn best_replies(&mut self, game: &ConnectFour) -> Vec<(Column, i8)> {
+        if game.is_over() {
+            return Vec::new();
+        }
+        game.children()
+            .map(|(column, child)| (column, self.score(&child)))
+            .collect()
+    }
+
+    /// Every legal move of the current player together with its score, as reported by
+    /// [`Self::analyze`], best move first. Equally good moves are ordered from the center outwards,
+    /// the right one first if two are equally far from the center (see [`TieBreak::CenterFirst`]).
+    /// So the first move is the one [`Self::best_move`] plays with that tie break. Empty if the
+    /// game is over.
+    pub fn ranked_moves(&mut self, game: &ConnectFour) -> Vec<(Column, i8)> {
+        let mut ranked: Vec<(Column, i8)> = self
+            .analyze(game)
+            .iter()
+            .enumerate()
+            .filter_map(|(index, score)| score.map(|score| (Column(index as u8), score)))
+            .collect();
+        ranked.sort_by_key(|&(column, score)| (-score, COLUMN_PRIORITY[column.0 as usize]));
+        ranked
+    }
+
+    /// Prints `game` like [`ConnectFour::print_to`], followed by a row with the score of each
+    /// column as reported by [`Self::analyze`]. Columns which can not be played show a `-` instead.
+    /// Cells, column labels and scores are padded to the width of the widest score (e.g. `-18`), so
+    /// each score lines up with its column.
+    pub fn render_analysis(&mut self, game: &ConnectFour, mut out: impl Write) -> io::Result<()> {
+        const WIDTH: usize = 3;
+        game.print_with_cell_width(WIDTH, &mut out)?;
+        let scores: String = self
+            .analyze(game)
+            .iter()
+            .map(|score| {
+                let score = score.map_or_else(|| "-".to_owned(), |score| score.to_string());
+                format!(" {score:<WIDTH$}")
+            })
+            .collect();
+        writeln!(out, "{}", scores.trim_end())
+    }
+
+    /// Difference between the scores of the best and the second best move, as scored by
+    /// [`Self::analyze`]. A large difference marks a critical decision, `0` means there are several
+    /// equally good moves. `None` if there are fewer than two legal moves.
+    pub fn move_sharpness(&mut self, game: &ConnectFour) -> Option<i8> {
+        let mut scores: Vec<i8> = self.analyze(game).into_iter().flatten().collect();
+        scores.sort_unstable_by(|a, b| b.cmp(a));
+        match scores.as_slice() {
+            [best, second, ..] => Some(best - second),
+            _ => None,
+        }
+    }
+
+    /// Same as [`Self::best_moves`], but scores each legal move on the rayon thread pool.
+    ///
+    /// Transposition tables can not be shared between threads, so each move is scored by its own
+    /// solver with its own transposition table. With up to seven legal moves this requires up to
+    /// seven times the memory of a single solver (about 450MiB), and the table of `self` is not
+    /// used. Positions explored by the workers are added to [`Self::nodes`].
+    #[cfg(feature = "rayon")]
+    pub fn best_moves_parallel(&mut self, game: &ConnectFour, best_moves: &mut Vec<Column>) {
+        use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+        if game.is_over() {
+            return;
+        }
+        let children: Vec<(Column, ConnectFour)> = game.children().collect();
+        let use_book = self.use_book;
+        let scores: Vec<(Column, i8, u64)> = children
+            .into_par_iter()
+            .map(|(column, child)| {
+                let mut solver = Solver {
+                    use_book,
+                    ..Solver::new()
+                };
+                let score = solver.score(&child);
+                (column, score, solver.nodes)
+            })
+            .collect();
+        self.nodes += scores.iter().map(|&(_, _, nodes)| nodes).sum::<u64>();
+        let min = scores.iter().map(|&(_, score, _)| score).min().unwrap();
+        best_moves.clear();
+        best_moves.extend(
+            scores
+                .iter()
+                .filter(|&&(_, score, _)| score == min)
+                .map(|&(column, _, _)| column),
+        );
+    }
+
+    /// Same as [`Self::best_moves_parallel`], but the moves are scored with `table`, which all
+    /// threads share, rather than a table of their own. Positions which come up in the search of
+    /// several moves are searched only once, and the memory is spent once, rather than seven times.
+    /// Reuse `table` for the following positions of a game. The table of `self` is not used.
+    #[cfg(feature = "rayon")]
+    pub fn best_moves_shared(
+        &mut self,
+        game: &ConnectFour,
+        table: &ConcurrentTranspositionTable,
+        best_moves: &mut Vec<Column>,
+    ) {
+        use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+        if game.is_over() {
+            return;
+        }
+        let children: Vec<(Column, ConnectFour)> = game.children().collect();
+        let use_book = self.use_book;
+        let scores: Vec<(Column, i8, u64)> = children
+            .into_par_iter()
+            .map(|(column, child)| {
+                let (mut nodes, mut table) = (0, table);
+                let score = use_book
+                    .then(|| precalculated_score(&child))
+                    .flatten()
+                    .or_else(|| search(&child, &mut table, &mut nodes, &NEVER_CANCELLED))
+                    .expect("Search is never cancelled");
+                (column, score, nodes)
+            })
+            .collect();
+        self.nodes += scores.iter().map(|&(_, _, nodes)| nodes).sum::<u64>();
+        let min = scores.iter().map(|&(_, score, _)| score).min().unwrap();
+        best_moves.clear();
+        best_moves.extend(
+            scores
+                .iter()
+                .filter(|&&(_, score, _)| score == min)
+                .map(|&(column, _, _)| column),
+        );
+    }
+
+    /// Fills `best_moves` with all the legal moves, which have the best strong score.
+    pub fn best_moves(&mut self, game: &ConnectFour, best_moves: &mut Vec<Column>) {
+        if game.is_over() {
+            return;
+        }
+        let mut min = i8::MAX;
+        for (column, child) in game.children() {
+            let score = self.score(&child);
+            match score.cmp(&min) {
+                Ordering::Less => {
+                    min = score; 
+                    best_moves.clear();
+                    best_moves.push(column);
+                },
+                Ordering::Equal => {
+                    best_moves.push(column);
+                },
+                Ordering::Greater => (),
+            };
+        }
+    }
+
+    /// Same as [`Self::best_moves`], but the equally good moves are ordered by `policy`, rather than
+    /// from left to right. Callers taking the first move decide which of them gets played this way.
+    pub fn best_moves_ordered(
+        &mut self,
+        game: &ConnectFour,
+        policy: TieBreak,
+        best_moves: &mut Vec<Column>,
+    ) {
+        self.best_moves(game, best_moves);
+        match policy {
+            TieBreak::Ascending => (),
+            TieBreak::CenterFirst => {
+                best_moves.sort_by_key(|column| COLUMN_PRIORITY[column.0 as usize])
+            }
+            TieBreak::Random(seed) => shuffle(&mut XorShift::new(seed), best_moves),
+        }
+    }
+
+    /// One of the [`Self::best_moves`]. Picked at random if the solver has been created with
+    /// [`Self::seeded`], otherwise the leftmost one, or the first one in the order set with
+    /// [`SolverBuilder::tie_break`]. `None` if the game is over.
+    pub fn best_move(&mut self, game: &ConnectFour) -> Option<Column> {
+        let mut best_moves = Vec::new();
+        self.best_moves_ordered(game, self.tie_break, &mut best_moves);
+        match &mut self.rng {
+            Some(rng) => choose(rng, &best_moves),
+            None => best_moves.first().copied(),
+        }
+    }
+
+    /// One of the [`Self::best_moves`], picked uniformly at random by `rng`. Play stays perfect, but
+    /// does not repeat itself like always taking the first best move does. `None` if the game is
+    /// over.
+    pub fn best_move_rng<R: Rng>(&mut self, game: &ConnectFour, rng: &mut R) -> Option<Column> {
+        let mut best_moves = Vec::new();
+        self.best_moves(game, &mut best_moves);
+        choose(rng, &best_moves)
+    }
+}
+
+/// Configures a [`Solver`] option by option, rather than picking one of its constructors. Unless
+/// changed, each option is the same as for [`Solver::new`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SolverBuilder {
+    table_entries: usize,
+    use_book: bool,
+    seed: Option<u64>,
+    tie_break: TieBreak,
+}
+
+impl SolverBuilder {
+    /// Builder for a solver like [`Solver::new`].
+    pub fn new() -> Self {
+        Self {
+            table_entries: 2 * DEFAULT_BUCKETS,
+            use_book: true,
+            seed: None,
+            tie_break: TieBreak::Ascending,
+        }
+    }
+
+    /// Number of positions the transposition table can hold, 8 Byte each. Two entries form a
+    /// bucket, so an odd number is rounded up. See [`Solver::with_capacity`]. Panics in
+    /// [`Self::build`] if `0`.
+    pub fn table_entries(mut self, table_entries: usize) -> Self {
+        self.table_entries = table_entries;
+        self
+    }
+
+    /// `false` to search positions with few stones, rather than looking up their precalculated
+    /// scores. See [`Solver::without_book`].
+    pub fn use_book(mut self, use_book: bool) -> Self {
+        self.use_book = use_book;
+        self
+    }
+
+    /// Seeds the generator [`Solver::best_move`] picks randomly among equally good moves with. See
+    /// [`Solver::seeded`]. Takes precedence over [`Self::tie_break`].
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Decides which of several equally good moves [`Solver::best_move`] picks: The first one in
+    /// this order.
+    pub fn tie_break(mut self, tie_break: TieBreak) -> Self {
+        self.tie_break = tie_break;
+        self
+    }
+
+    /// Solver with the options set so far
+    pub fn build(&self) -> Solver {
+        Solver {
+            use_book: self.use_book,
+            rng: self.seed.map(XorShift::new),
+            tie_break: self.tie_break,
+            ..Solver::with_capacity(self.table_entries.div_ceil(2))
+        }
+    }
+}
+
+impl Default for SolverBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Calculates the score of a connect four game. The score is set up so always picking the move with
+/// the lowest score results in perfect play. Perfect meaning winning as fast as possible, drawing
+/// or loosing as late as possible.
+///
+/// A positive score means the player who can put in the next stone can win. Positions which can be
+/// won faster are scored higher. The score is 1 if the current player can win with his last stone.
+/// Two if he can win with his second to last stone and so on. A score of zero means the game will
+/// end in a draw if both players play perfectly. A negative score means the opponent (the player
+/// which is not putting in the next stone) is winnig. It is `-1` if the opponent is winning with
+/// his last stone. `-2` if he is winning second to last stone and so on.
+pub fn score(game: &ConnectFour) -> i8 {
+    Solver::new().score(game)
+}
+
+/// Indices which should get explored first get smaller values. Explore center moves first.
+/// These are better on average. This allows for faster pruning.
+const COLUMN_PRIORITY: [u8; 7] = [6, 4, 2, 0, 1, 3, 5];
+
+/// Order of equally good moves, see [`Solver::best_moves_ordered`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TieBreak {
+    /// From the leftmost to the rightmost column, like [`Solver::best_moves`].
+    Ascending,
+    /// Center column first, then alternating outwards, starting to the right of the center. This is
+    /// the order in which the solver explores moves.
+    CenterFirst,
+    /// Shuffled. The same seed yields the same order for the same moves.
+    Random(u64),
+}
+
+/// Where the score returned by [`Solver::score_source`] came from.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ScoreSource {
+    /// Looked up in the precalculated scores of positions with few stones, which are exact.
+    Book,
+    /// Found in the transposition table as a bound, which leaves no room for any other score. No
+    /// search required.
+    TableExact,
+    /// Found in the transposition table, but only as an upper bound. The search started from it,
+    /// and is likely to be faster than without it.
+    TableBound,
+    /// Not known to the solver before, so the position has been searched from scratch. This
+    /// includes positions decided by the next stone, which take no search at all.
+    Searched,
+}
+
+/// Outcome of a game for the current player, assuming both players play perfectly.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    Win,
+    Draw,
+    Loss,
+}
+
+/// Result of [`Solver::status`]. Who wins if both players play perfectly from here on.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameStatus {
+    /// The player to move can force a win.
+    CurrentPlayerWins,
+    /// Neither player can force a win.
+    Drawn,
+    /// The opponent of the player to move can force a win, or has already won.
+    CurrentPlayerLoses,
+}
+
+/// Result of [`Solver::solve`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Solution {
+    /// Same as returned by [`Solver::score`].
+    pub score: i8,
+    /// Leftmost move which achieves `score`. `None` if the game is over.
+    pub best_move: Option<Column>,
+    /// Moves of both players, starting with `best_move`, until the end of the game with perfect
+    /// play. Empty if the game is over.
+    pub principal_variation: Vec<Column>,
+}
+
+/// Interprets a `score` of `game`, as returned by [`score`] or [`Solver::analyze`]. Returns the
+/// outcome for the current player and the number of stones put into the board until the game ends,
+/// counting the next stone and the final one.
+pub fn interpret_score(game: &ConnectFour, score: i8) -> (Outcome, u8) {
+    let stones = game.stones() as i8;
+    if score == 0 {
+        return (Outcome::Draw, game.remaining_moves());
+    }
+    // The current player puts in every odd stone from now on. Their opponent every even one.
+    let (outcome, winner_parity) = if score > 0 {
+        (Outcome::Win, (stones + 1) % 2)
+    } else {
+        (Outcome::Loss, stones % 2)
+    };
+    // Invert `score_from_num_stones`, knowing the parity of the winning stone.
+    let remaining_stones = score.abs() - 1;
+    let last_stone = if winner_parity == 0 {
+        TOTAL_CELLS as i8 - 2 * remaining_stones
+    } else {
+        TOTAL_CELLS as i8 - 1 - 2 * remaining_stones
+    };
+    (outcome, (last_stone - stones) as u8)
+}
+
+/// Score of positions which are already won, or which can be won with the next stone. `None` for
+/// all other positions. `alpha_beta` assumes that the next move can not win the game, so these need
+/// to be handled before.
+///
+/// Positions in which nobody can complete a line anymore are scored as a draw right away, since
+/// searching them may take long if many cells are left.
+fn immediate_score(game: &ConnectFour) -> Option<i8> {
+    if game.is_victory() {
+        return Some(score_from_num_stones(game.stones() as i8));
+    }
+    if game.can_win_in_next_move() {
+        return Some(-score_from_num_stones(game.stones() as i8 + 1));
+    }
+    if game.is_dead() {
+        return Some(0);
+    }
+    None
+}
+
+impl ConnectFour {
+    /// Lower and upper bound for the [`score`] of the position, known from the number of stones
+    /// alone, without any search. The score is somewhere in between, both bounds included. The
+    /// lower bound stands for losing to the next stone of the opponent, the upper one for winning
+    /// with the next stone. Both are the score if the game is already won.
+    pub fn score_bounds(&self) -> (i8, i8) {
+        if self.is_victory() {
+            let score = score_from_num_stones(self.stones() as i8);
+            return (score, score);
+        }
+        let min = -(self.remaining_moves() as i8) / 2;
+        let max = (self.remaining_moves() as i8 + 1) / 2;
+        (min, max)
+    }
+}
+
+/// Score of `game` without looking at the precalculated scores, narrowing it down with null window
+/// searches. `None` if cancelled.
+fn search(
+    game: &ConnectFour,
+    table: &mut impl ScoreCache,
+    nodes: &mut u64,
+    cancel: &AtomicBool,
+) -> Option<i8> {
+    if let Some(score) = immediate_score(game) {
+        return Some(score);
+    }
+    table.set_root_stones(game.stones());
+
+    let (mut min, mut max) = game.score_bounds();
+
+    // Iterative deepening
+    while min < max {
+        if cancel.load(atomic::Ordering::Relaxed) {
+            return None;
+        }
+        let median = min + (max - min) / 2;
+        let alpha = if median <= 0 && min / 2 < median {
+            // Explore loosing path deeper
+            min / 2
+        } else if median >= 0 && max / 2 > median {
+            // Explore winning path deeper
+            max / 2
+        } else {
+            median
+        };
+        let result = alpha_beta(
+            game,
+            game.openings(),
+            alpha,
+            alpha + 1,
+            table,
+            nodes,
+            cancel,
+        )?;
+        if result <= alpha {
+            max = result;
+        } else {
+            min = result;
+        }
+    }
+    debug_assert_eq!(min, max);
+    Some(min)
+}
+
+/// Score of the position with alepha beta pruning.
+///
+/// Assumes that position can not be won in a single move. Assumes that position is not won position
+/// already.
+///
+/// * If actual score is smaller than alpha then: actual score <= return value <= alpha
+/// * If actual score is bigger than beta then: actual score >= return value >= beta
+/// * If score is within alpha beta window precise score is returned
+///
+/// If alpha is higher (or equal) than the score of this position, we can prune this position,
+/// because the current player would not play this route, since he is guaranteed to achieve a better
+/// outcome with some other play.
+///
+/// Similarly if this positions score is higher than beta we can prune it, since the opponent would
+/// choose a different line of play, which leavs him in a better position.
+///
+/// Alpha is a lower bound on what the current player can expect. Beta is as upper bound on what he
+/// can expect.
+///
+/// `openings` must be the openings of `game`. They are passed in, since the parent position already
+/// calculated them for move ordering.
+///
+/// Every `CANCEL_CHECK_INTERVAL` positions `cancel` is checked. If it is set, the search returns
+/// `None` without writing any more entries into the transposition table. The entries written so
+/// far stay valid.
+fn alpha_beta(
+    game: &ConnectFour,
+    openings: u64,
+    mut alpha: i8,
+    mut beta: i8,
+    cached_beta: &mut impl ScoreCache,
+    nodes: &mut u64,
+    cancel: &AtomicBool,
+) -> Option<i8> {
+    debug_assert!(alpha < beta);
+    debug_assert!(!game.can_win_in_next_move());
+    *nodes += 1;
+    if nodes.is_multiple_of(CANCEL_CHECK_INTERVAL) && cancel.load(atomic::Ordering::Relaxed) {
+        return None;
+    }
+
+    let possibilities = game.non_loosing_moves_with(openings);
+    if possibilities.is_empty() {
+        // If there are no possibilities for the current player not to loose, the opponent wins.
+        return Some(score_from_num_stones(game.stones() as i8 + 2));
+    }
+
+    // Check for draw
+    if game.remaining_moves() <= 2 {
+        return Some(0);
+    }
+
+    // Opponent can not win within one move, this gives us a lower bound for the score
+    alpha = max(alpha, score_from_num_stones(game.stones() as i8 + 4));
+    if alpha >= beta {
+        return Some(alpha);
+    }
+
+    // We may also find an upper bound in the cache. If not we use the fact that we know we can not
+    // win with our next stone, which puts the fastest possible win at least three stones away.
+    let upper_bound_beta = cached_beta
+        .get(cached_beta.key_of(game))
+        .unwrap_or_else(|| -score_from_num_stones(game.stones() as i8 + 3));
+    beta = min(beta, upper_bound_beta);
+    if alpha >= beta {
+        return Some(beta);
+    }
+
+    let mut move_explorer = MoveExplorer::new();
+    for col in 0..7 {
+        if possibilities.contains(col) {
+            move_explorer.add(col, game);
+        }
+    }
+    move_explorer.sort();
+
+    // We play the position which is the worst for our opponent
+    for (position, openings) in move_explorer.next_positions() {
+        // Score from the perspective of the current player is the negative of the opponents.
+        let score = -alpha_beta(&position, openings, -beta, -alpha, cached_beta, nodes, cancel)?;
+        // prune the exploration if we find a possible move better than what we were looking for.
+        if score >= beta {
+            return Some(score);
+        }
+        // We only need to search for positions, which are better than the best so far.
+        alpha = max(alpha, score);
+    }
+
+    // save the upper bound of the position
+    cached_beta.put(cached_beta.key_of(game), alpha, game.stones());
+    Some(alpha)
+}
+
+/// Negamax search with alpha beta pruning, which stops `depth` stones after `game` and scores the
+/// positions there as a draw. Unlike `alpha_beta` it handles any position, including finished
+/// games. See [`Solver::score_limited`].
+fn depth_limited(game: &ConnectFour, depth: u8, mut alpha: i8, beta: i8, nodes: &mut u64) -> i8 {
+    *nodes += 1;
+    if depth == 0 && !game.is_victory() {
+        return 0;
+    }
+    if let Some(score) = immediate_score(game) {
+        return score;
+    }
+    // The next stone can not win, so with only one stone left to look at, the position is open.
+    if game.is_over() || depth == 1 {
+        return 0;
+    }
+    // Central columns are part of more lines, so trying them first prunes more.
+    for col in [3, 2, 4, 1, 5, 0, 6].map(Column::from_index) {
+        let mut child = *game;
+        if !child.play(col) {
+            continue;
+        }
+        let score = -depth_limited(&child, depth - 1, -beta, -alpha, nodes);
+        if score >= beta {
+            return score;
+        }
+        alpha = max(alpha, score);
+    }
+    alpha
+}
+
+/// Number of positions `alpha_beta` explores between two checks for cancellation. Power of two, so
+/// the check is cheap.
+const CANCEL_CHECK_INTERVAL: u64 = 1 << 12;
+
+/// Passed to `alpha_beta` for searches which can not be cancelled.
+static NEVER_CANCELLED: AtomicBool = AtomicBool::new(false);
+
+/// Number of stones of the position `key` has been created from by [`ConnectFour::encode`].
+pub(crate) fn stones_of_key(key: u64) -> u8 {
+    (0..7)
+        .map(|column| {
+            // Stones of the column (all ones from the bottom) plus the ones of the player who put
+            // in the last stone.
+            let bits = (key >> (7 * column)) & 0b111_1111;
+            if (bits + 1).is_power_of_two() {
+                // None of them belongs to the last player.
+                bits.count_ones() as u8
+            } else {
+                // The highest bit is the one above the topmost stone.
+                (u64::BITS - 1 - bits.leading_zeros()) as u8
+            }
+        })
+        .sum()
+}
+
+/// Score from the perspective of the current player (who can no longer move, because the game is
+/// over), assuming the last stone won after `num_stones`.
+fn score_from_num_stones(num_stones: i8) -> i8 {
+    // Remaining stones of the winning player.
+    let remaining_stones = (TOTAL_CELLS as i8 - num_stones) / 2;
+    // Score is from the perspective of the moving player. So if the current position is a win, it
+    // is negative.
+    -(remaining_stones + 1)
+}
+
+/// Stack allocated container for possible moves. Iterates over moves in a fashion which allows to
+/// prune the search tree sooner.
+struct MoveExplorer {
+    /// Up to seven indices are possible. Store index, score, position and the openings of the
+    /// position. The openings are needed for both the score and exploring the position later, so we
+    /// only calculate them once.
+    col_indices: [(u8, u32, ConnectFour, u64); 7],
+    /// Up to this index the moves are valid.
+    len: usize,
+}
+
+impl MoveExplorer {
+    pub fn new() -> Self {
+        Self {
+            col_indices: [(0, 0, ConnectFour::new(), 0); 7],
+            len: 0,
+        }
+    }
+
+    pub fn add(&mut self, col_index: u8, from: &ConnectFour) {
+        let mut next_position = *from;
+        // The search only explores positions in which the current player can not win immediately.
+        let is_legal = next_position.play_non_winning(Column::from_index(col_index));
+        debug_assert!(is_legal);
+        let openings = next_position.openings();
+        let score = next_position.heuristic_with(openings);
+        self.col_indices[self.len] = (col_index, score, next_position, openings);
+        self.len += 1;
+    }
+
+    pub fn sort(&mut self) {
+        self.col_indices[..self.len].sort_unstable_by(|a, b| {
+            // sort by score first, then by column priority. We prefer higher scores, therfore a, b
+            // are switched in order.
+            b.1.cmp(&a.1)
+                .then_with(|| COLUMN_PRIORITY[a.0 as usize].cmp(&COLUMN_PRIORITY[b.0 as usize]))
+        });
+    }
+
+    /// Positions in the order they should be explored, together with their openings.
+    pub fn next_positions(&self) -> impl Iterator<Item = (ConnectFour, u64)> + '_ {
+        self.col_indices[..self.len]
+            .iter()
+            .map(|&(_, _, pos, openings)| (pos, openings))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{read_dataset, ConnectFour};
+
+    use super::{stones_of_key, MoveExplorer};
+
+    #[test]
+    fn number_of_stones_from_key() {
+        for moves in ["", "4", "44", "1234567", "5655663642443", "777777"] {
+            let game = ConnectFour::from_move_list(moves);
+            assert_eq!(game.stones(), stones_of_key(game.encode()), "{moves}");
+        }
+    }
+
+    /// Heuristic and openings reused by the move explorer must be identical to calculating them
+    /// from scratch for the explored position.
+    #[test]
+    fn move_explorer_reuses_identical_openings() {
+        for entry in read_dataset("./tests/Test_L2_R1").unwrap() {
+            let (_, game, _) = entry.unwrap();
+            if game.can_win_in_next_move() {
+                // The search does not explore these.
+                continue;
+            }
+
+            let mut move_explorer = MoveExplorer::new();
+            for column in game.legal_moves() {
+                move_explorer.add(column.0, &game);
+            }
+
+            for &(_, score, position, openings) in &move_explorer.col_indices[..move_explorer.len] {
+                assert_eq!(position.openings(), openings);
+                assert_eq!(position.heuristic(), score);
+            }
+        }
+    }
+}
@@ -1,58 +1,418 @@
+use std::io;
+#[cfg(feature = "rayon")]
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+use crate::{bitboard::mirror_columns, solver::stones_of_key, ConnectFour};
+
+/// Upper bounds of scores found by the search. Implemented by the tables the search can use.
+pub trait ScoreCache {
+    /// Key to `put` and `get` the score of `game` with.
+    fn key_of(&self, game: &ConnectFour) -> u64;
+
+    fn get(&self, board: u64) -> Option<i8>;
+
+    fn put(&mut self, board: u64, score: i8, stones: u8);
+
+    /// Number of stones of the position the next search starts from. See
+    /// [`TranspositionTable::set_root_stones`].
+    fn set_root_stones(&mut self, stones: u8);
+}
+
 /// Stores the score of board positions, so we do not need to recompute it, if the same position
 /// comes up again.
+///
+/// Each bucket holds two slots. The first one is "depth preferred": It only gets replaced by
+/// positions with at most as many stones in them, since positions with fewer stones span larger
+/// subtrees and are more expensive to recompute. The second one is "always replace" and holds
+/// whatever has been put into the bucket most recently, if it did not make it into the first slot.
+///
+/// Entries age as the game moves on. Positions with fewer stones than the root of the current
+/// search can not be reached anymore. They are considered stale and give way to any new entry, no
+/// matter how many stones the new position has. See [`Self::set_root_stones`].
 pub struct TranspositionTable {
-    // Stores the last 32 bits of the board, i.e. board modulo 2 ^ 32.
-    keys: Vec<u32>,
-    scores: Vec<i8>,
+    // Two consecutive entries form a bucket. Each entry packs the full 49 bit key of the board into
+    // its lowest bits, followed by the score (8 bits) and the number of stones (6 bits) of the
+    // position. The number of stones is used to decide which entry to evict.
+    entries: Vec<u64>,
+    /// Number of stones of the position at the root of the current search. Acts as the generation
+    /// of the table: Every move in a real game increases it.
+    root_stones: u8,
+    /// `true` if a position and its mirror image share their entry. See [`Self::key_of`].
+    canonical_keys: bool,
 }
 
 impl TranspositionTable {
+    /// Offset of the depth preferred slot within a bucket.
+    const DEEP: usize = 0;
+    /// Offset of the always replace slot within a bucket.
+    const ALWAYS: usize = 1;
+    /// Largest number of buckets [`Self::load`] accepts. Tables of this size take 64GiB.
+    pub const MAX_LOADED_BUCKETS: u64 = 1 << 32;
+
+    /// `capacity` is the number of buckets. Each bucket holds two entries.
     pub fn new(capacity: usize) -> Self {
-        // Capacity must be odd, so it is a coprime (i.e. it has no common prime factors) a power of
-        // two.
-        assert!(capacity % 2 == 1);
-        // 49 Bits uniquely encode the board. => Max key is 2 ^ 49.
-        // capacity is coprime to 2 ^ 32, and S * 2 ^ 32 greater than the max possible full key, the
-        // chinese remainder theorem guarantees that the index, key pair is unique. 
-        assert!(capacity * (2 ^ 32) > 2 ^ 49);
+        // We store the full key of the board, so two different positions can never be mistaken for
+        // each other, no matter the capacity. Still, it must not be zero.
+        assert!(capacity > 0);
         Self {
-            // We use 0, to represent a cache miss
-            keys: vec![0; capacity],
-            scores: vec![0; capacity],
+            // We use a key of 0, to represent a cache miss
+            entries: vec![0; 2 * capacity],
+            root_stones: 0,
+            canonical_keys: false,
         }
     }
 
-    pub fn put(&mut self, board: u64, score: i8) {
-        let index = self.index(board);
-        self.keys[index] = Self::key(board);
-        self.scores[index] = score;
+    pub fn put(&mut self, board: u64, score: i8, stones: u8) {
+        let bucket = self.bucket(board);
+        let key = Self::key(board);
+        let deep = self.entries[bucket + Self::DEEP];
+        let entry = entry(key, score, stones);
+        if replaces_deep(deep, key, stones, self.root_stones) {
+            self.entries[bucket + Self::DEEP] = entry;
+            // Do not keep a stale duplicate of this position in the other slot.
+            if entry_key(self.entries[bucket + Self::ALWAYS]) == key {
+                self.entries[bucket + Self::ALWAYS] = 0;
+            }
+        } else {
+            self.entries[bucket + Self::ALWAYS] = entry;
+        }
     }
 
     pub fn get(&self, board: u64) -> Option<i8> {
-        let index = self.index(board);
-        let found_key = self.keys[index];
-        if found_key == Self::key(board) {
-            // Hit
-            Some(self.scores[index])
+        let bucket = self.bucket(board);
+        let key = Self::key(board);
+        self.entries[bucket..bucket + 2]
+            .iter()
+            // Hit, if any of the two entries matches. Otherwise miss.
+            .find(|&&entry| entry_key(entry) == key)
+            .map(|&entry| entry_score(entry))
+    }
+
+    /// Removes all entries, as if the table had just been created.
+    pub fn clear(&mut self) {
+        self.entries.fill(0);
+        self.root_stones = 0;
+    }
+
+    /// Store a position and its mirror image under the same key from now on. See [`Self::key_of`].
+    pub fn set_canonical_keys(&mut self, canonical_keys: bool) {
+        self.canonical_keys = canonical_keys;
+    }
+
+    /// Key to `put` and `get` the score of `game` with. This is [`ConnectFour::encode`], or
+    /// [`ConnectFour::canonical_encode`] if the table uses canonical keys.
+    pub fn key_of(&self, game: &ConnectFour) -> u64 {
+        self.key_of_encoded(game.encode())
+    }
+
+    /// Like [`Self::key_of`], but for a position already encoded with [`ConnectFour::encode`].
+    pub fn key_of_encoded(&self, key: u64) -> u64 {
+        if self.canonical_keys {
+            // Each column is encoded on its own, so this is the same as encoding the mirror image.
+            key.min(mirror_columns::<7, 6>(key))
         } else {
-            // Miss
-            None
+            key
+        }
+    }
+
+    /// Tell the table the number of stones of the position the next search starts from. Entries
+    /// with fewer stones are stale from now on. They can still be found with `get`, but are the
+    /// first to be evicted. A smaller number than before (e.g. because a new game started) is fine
+    /// and just makes the old entries relevant again.
+    pub fn set_root_stones(&mut self, stones: u8) {
+        self.root_stones = stones;
+    }
+
+    /// Writes the number of buckets, followed by every entry which is not empty, in the order of
+    /// their slots. All numbers are 64 Bit little endian.
+    pub fn save(&self, mut out: impl io::Write) -> io::Result<()> {
+        let num_buckets = self.entries.len() as u64 / 2;
+        out.write_all(&num_buckets.to_le_bytes())?;
+        for &entry in self.entries.iter().filter(|&&entry| entry != 0) {
+            out.write_all(&entry.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Reads a table written by [`Self::save`]. Fails with [`io::ErrorKind::InvalidData`] if `input`
+    /// is not such a table, including tables with more than [`Self::MAX_LOADED_BUCKETS`] buckets or
+    /// too large to be allocated.
+    pub fn load(mut input: impl io::Read) -> io::Result<Self> {
+        let invalid = |message| io::Error::new(io::ErrorKind::InvalidData, message);
+        let mut bytes = Vec::new();
+        input.read_to_end(&mut bytes)?;
+        if bytes.len() < 8 || bytes.len() % 8 != 0 {
+            return Err(invalid("Length of transposition table must be a multiple of 8 bytes."));
+        }
+        let mut numbers = bytes
+            .chunks_exact(8)
+            .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()));
+        let num_buckets = numbers.next().unwrap();
+        if num_buckets == 0 {
+            return Err(invalid("Transposition table must have at least one bucket."));
+        }
+        if num_buckets > Self::MAX_LOADED_BUCKETS {
+            return Err(invalid("Transposition table has too many buckets."));
+        }
+        if numbers.len() as u64 > 2 * num_buckets {
+            return Err(invalid("More entries than fit into the transposition table."));
+        }
+        // Fail rather than abort, if the header asks for more memory than there is.
+        let mut entries = Vec::new();
+        entries
+            .try_reserve_exact(2 * num_buckets as usize)
+            .map_err(|_| invalid("Not enough memory for transposition table."))?;
+        entries.resize(2 * num_buckets as usize, 0);
+        let mut table = Self {
+            entries,
+            root_stones: 0,
+            canonical_keys: false,
+        };
+        for entry in numbers {
+            if !is_valid_entry(entry) {
+                return Err(invalid("Invalid entry in transposition table."));
+            }
+            let bucket = table.bucket(entry_key(entry) - 1);
+            // Entries of the depth preferred slot have been written first. The always replace slot
+            // is never in use without the other one.
+            let slot = if table.entries[bucket + Self::DEEP] == 0 {
+                Self::DEEP
+            } else if table.entries[bucket + Self::ALWAYS] == 0 {
+                Self::ALWAYS
+            } else {
+                return Err(invalid("More than two entries for the same bucket."));
+            };
+            table.entries[bucket + slot] = entry;
+        }
+        Ok(table)
+    }
+
+    fn key(board: u64) -> u64 {
+        stored_key(board)
+    }
+
+    /// Index of the first entry of the bucket `board` belongs to.
+    fn bucket(&self, board: u64) -> usize {
+        let num_buckets = self.entries.len() as u64 / 2;
+        (board % num_buckets) as usize * 2
+    }
+}
+
+impl ScoreCache for TranspositionTable {
+    fn key_of(&self, game: &ConnectFour) -> u64 {
+        self.key_of(game)
+    }
+
+    fn get(&self, board: u64) -> Option<i8> {
+        self.get(board)
+    }
+
+    fn put(&mut self, board: u64, score: i8, stones: u8) {
+        self.put(board, score, stones)
+    }
+
+    fn set_root_stones(&mut self, stones: u8) {
+        self.set_root_stones(stones)
+    }
+}
+
+/// Transposition table which can be shared by several threads searching at the same time, see
+/// [`crate::Solver::best_moves_shared`]. Each thread benefits from the positions the others have
+/// searched already, and the memory is only spent once.
+///
+/// Buckets and entries are laid out like the ones of the table of a single [`crate::Solver`]. Each
+/// entry is a single atomic word holding the full key of the position next to its score, so a
+/// thread never reads a score belonging to another position. Threads do not wait for each other:
+/// If two of them write into the same bucket at the same time, one of the entries may get lost.
+/// Entries only speed up the search, so this costs some time, but never changes a score.
+#[cfg(feature = "rayon")]
+pub struct ConcurrentTranspositionTable {
+    entries: Vec<AtomicU64>,
+    /// See [`TranspositionTable::set_root_stones`]. Threads searching children of the same
+    /// position set the same number, give or take one.
+    root_stones: AtomicU8,
+}
+
+#[cfg(feature = "rayon")]
+impl ConcurrentTranspositionTable {
+    /// `capacity` is the number of buckets. Each bucket holds two entries of 8 Byte. Panics if
+    /// `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0);
+        Self {
+            entries: (0..2 * capacity).map(|_| AtomicU64::new(0)).collect(),
+            root_stones: AtomicU8::new(0),
         }
     }
 
-    fn key(board: u64) -> u32 {
-        board as u32
+    /// Index of the first entry of the bucket `board` belongs to.
+    fn bucket(&self, board: u64) -> usize {
+        let num_buckets = self.entries.len() as u64 / 2;
+        (board % num_buckets) as usize * 2
+    }
+}
+
+/// Shared references do the writing, so any number of threads can search with the same table.
+#[cfg(feature = "rayon")]
+impl ScoreCache for &ConcurrentTranspositionTable {
+    fn key_of(&self, game: &ConnectFour) -> u64 {
+        game.encode()
+    }
+
+    fn get(&self, board: u64) -> Option<i8> {
+        let bucket = self.bucket(board);
+        self.entries[bucket..bucket + 2]
+            .iter()
+            .map(|entry| entry.load(Ordering::Relaxed))
+            .find(|&entry| entry_key(entry) == stored_key(board))
+            .map(entry_score)
     }
 
-    fn index(&self, board: u64) -> usize {
-        (board % self.keys.len() as u64) as usize
+    fn put(&mut self, board: u64, score: i8, stones: u8) {
+        let bucket = self.bucket(board);
+        let deep = self.entries[bucket + TranspositionTable::DEEP].load(Ordering::Relaxed);
+        let root_stones = self.root_stones.load(Ordering::Relaxed);
+        let key = stored_key(board);
+        let entry = entry(key, score, stones);
+        if replaces_deep(deep, key, stones, root_stones) {
+            self.entries[bucket + TranspositionTable::DEEP].store(entry, Ordering::Relaxed);
+            // Another thread may write the same position in between. A stale duplicate is still a
+            // valid bound, just a less tight one.
+            let always = &self.entries[bucket + TranspositionTable::ALWAYS];
+            if entry_key(always.load(Ordering::Relaxed)) == key {
+                always.store(0, Ordering::Relaxed);
+            }
+        } else {
+            self.entries[bucket + TranspositionTable::ALWAYS].store(entry, Ordering::Relaxed);
+        }
+    }
+
+    fn set_root_stones(&mut self, stones: u8) {
+        self.root_stones.store(stones, Ordering::Relaxed);
     }
 }
 
+/// `true` if a new entry for `key` goes into the depth preferred slot, currently holding `deep`.
+fn replaces_deep(deep: u64, key: u64, stones: u8, root_stones: u8) -> bool {
+    entry_key(deep) == 0
+        || entry_key(deep) == key
+        || entry_stones(deep) < root_stones
+        || stones <= entry_stones(deep)
+}
+
+/// 49 Bits uniquely encode the board. See [`crate::ConnectFour::encode`].
+const KEY_BITS: u32 = 49;
+
+/// Key of `board` as stored in an entry. An entry of `0` is empty, yet the empty board is encoded
+/// as `0`, too. So the key is offset by one. Keys of boards stay below `2^49 - 1`, so it still fits.
+fn stored_key(board: u64) -> u64 {
+    debug_assert!(board < (1 << KEY_BITS) - 1);
+    board + 1
+}
+
+fn entry(key: u64, score: i8, stones: u8) -> u64 {
+    key | (score as u8 as u64) << KEY_BITS | (stones as u64) << (KEY_BITS + 8)
+}
+
+fn entry_key(entry: u64) -> u64 {
+    entry & ((1 << KEY_BITS) - 1)
+}
+
+fn entry_score(entry: u64) -> i8 {
+    (entry >> KEY_BITS) as u8 as i8
+}
+
+fn entry_stones(entry: u64) -> u8 {
+    (entry >> (KEY_BITS + 8)) as u8
+}
+
+/// `true` if `entry` could have been written by [`TranspositionTable::put`]: It is not empty, its key
+/// has the layout of [`ConnectFour::encode`] and its number of stones matches the key.
+fn is_valid_entry(entry: u64) -> bool {
+    let Some(board) = entry_key(entry).checked_sub(1) else {
+        return false;
+    };
+    // Seven bits per column. All of them set would be a column with seven stones.
+    let full_column = (0..7).any(|column| (board >> (7 * column)) & 0b111_1111 == 0b111_1111);
+    !full_column && entry_stones(entry) == stones_of_key(board)
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::ConnectFour;
-    use super::TranspositionTable;
+    use std::io;
+
+    use crate::{solver::stones_of_key, ConnectFour};
+    use super::{entry, stored_key, TranspositionTable};
+
+    #[test]
+    fn save_and_load() {
+        let capacity = 131101;
+        let deep = ConnectFour::from_move_list("4");
+        let shallow_key = deep.encode() + capacity as u64;
+        let mut cache = TranspositionTable::new(capacity);
+        cache.put(deep.encode(), 3, deep.stones());
+        cache.put(shallow_key, 5, stones_of_key(shallow_key));
+
+        let mut bytes = Vec::new();
+        cache.save(&mut bytes).unwrap();
+        let loaded = TranspositionTable::load(&bytes[..]).unwrap();
+
+        // Number of buckets and two entries
+        assert_eq!(3 * 8, bytes.len());
+        assert_eq!(cache.entries, loaded.entries);
+    }
+
+    #[test]
+    fn load_rejects_truncated_table() {
+        let mut bytes = Vec::new();
+        TranspositionTable::new(127).save(&mut bytes).unwrap();
+
+        assert!(TranspositionTable::load(&bytes[..5]).is_err());
+    }
+
+    #[test]
+    fn load_rejects_too_many_buckets() {
+        let num_buckets = TranspositionTable::MAX_LOADED_BUCKETS + 1;
+
+        let error = TranspositionTable::load(&num_buckets.to_le_bytes()[..]).err().unwrap();
+
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+    }
+
+    #[test]
+    fn load_rejects_more_entries_than_buckets_hold() {
+        let game = ConnectFour::from_move_list("4");
+        let entry = entry(stored_key(game.encode()), 1, game.stones());
+        let bytes: Vec<u8> = [1, entry, entry, entry]
+            .iter()
+            .flat_map(|number: &u64| number.to_le_bytes())
+            .collect();
+
+        let error = TranspositionTable::load(&bytes[..]).err().unwrap();
+
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+    }
+
+    #[test]
+    fn load_rejects_invalid_entries() {
+        let game = ConnectFour::from_move_list("4");
+        let invalid_entries = [
+            // Empty slots are not saved
+            0,
+            // Wrong number of stones
+            entry(stored_key(game.encode()), 1, game.stones() + 1),
+            // Seven stones in the first column
+            entry(stored_key(0b111_1111), 1, 7),
+        ];
+        for invalid_entry in invalid_entries {
+            let mut bytes = 127u64.to_le_bytes().to_vec();
+            bytes.extend_from_slice(&invalid_entry.to_le_bytes());
+
+            let error = TranspositionTable::load(&bytes[..]).err().unwrap();
+
+            assert_eq!(io::ErrorKind::InvalidData, error.kind());
+        }
+    }
 
     #[test]
     fn cache_hit() {
@@ -62,11 +422,24 @@ mod tests {
         // 131101 next prime after 131073 which is the smallest valid number for the transposition
         // table to work correctly.
         let mut cache = TranspositionTable::new(131101);
-        cache.put(position.encode(), score);
+        cache.put(position.encode(), score, position.stones());
 
         assert_eq!(cache.get(position.encode()), Some(score));
     }
 
+    #[test]
+    fn empty_board_is_not_mistaken_for_empty_slot() {
+        // The empty board is encoded as `0`, like an empty slot.
+        let empty_board = ConnectFour::new().encode();
+        let mut cache = TranspositionTable::new(127);
+
+        assert_eq!(cache.get(empty_board), None);
+
+        cache.put(empty_board, 1, 0);
+
+        assert_eq!(cache.get(empty_board), Some(1));
+    }
+
     #[test]
     fn cache_miss() {
         let position = ConnectFour::from_move_list("5655663642443");
@@ -74,8 +447,91 @@ mod tests {
         let score = 15;
 
         let mut cache = TranspositionTable::new(131101);
-        cache.put(position.encode(), score);
+        cache.put(position.encode(), score, position.stones());
 
         assert_eq!(cache.get(other_position.encode()), None);
     }
+
+    #[test]
+    fn shallow_position_does_not_evict_deep_one() {
+        let capacity = 131101;
+        let deep = ConnectFour::from_move_list("4");
+        // Construct a key which maps into the same bucket, but has more stones.
+        let shallow_key = deep.encode() + capacity as u64;
+        let shallow_stones = 20;
+
+        let mut cache = TranspositionTable::new(capacity);
+        cache.put(deep.encode(), 3, deep.stones());
+        cache.put(shallow_key, 5, shallow_stones);
+        // A third entry for the bucket evicts the shallow one, but not the deep one.
+        cache.put(shallow_key + capacity as u64, 7, shallow_stones);
+
+        assert_eq!(cache.get(deep.encode()), Some(3));
+        assert_eq!(cache.get(shallow_key), None);
+        assert_eq!(cache.get(shallow_key + capacity as u64), Some(7));
+    }
+
+    #[test]
+    fn boards_colliding_in_low_bits_do_not_alias() {
+        // Both positions only differ in the fifth column. Bits above 32 are in the fifth column or
+        // to the right of it, so the lower 32 bits of their keys are identical.
+        let position = ConnectFour::from_move_list("16");
+        let other_position = ConnectFour::from_move_list("17");
+        assert_eq!(position.encode() as u32, other_position.encode() as u32);
+        // The keys differ by 2^36 * 127, so with 127 buckets both map into the same one.
+        let capacity = 127;
+        assert_eq!(position.encode() % capacity, other_position.encode() % capacity);
+
+        let mut cache = TranspositionTable::new(capacity as usize);
+        cache.put(position.encode(), 15, position.stones());
+
+        assert_eq!(cache.get(other_position.encode()), None);
+        assert_eq!(cache.get(position.encode()), Some(15));
+    }
+
+    #[test]
+    fn stale_deep_position_gets_evicted() {
+        let capacity = 131101;
+        let deep = ConnectFour::from_move_list("4");
+        let shallow_key = deep.encode() + capacity as u64;
+        let shallow_stones = 20;
+
+        let mut cache = TranspositionTable::new(capacity);
+        cache.put(deep.encode(), 3, deep.stones());
+        // The game moved on, the deep position can not be reached anymore.
+        cache.set_root_stones(10);
+        cache.put(shallow_key, 5, shallow_stones);
+        cache.put(shallow_key + capacity as u64, 7, shallow_stones + 1);
+
+        assert_eq!(cache.get(deep.encode()), None);
+        assert_eq!(cache.get(shallow_key), Some(5));
+        assert_eq!(cache.get(shallow_key + capacity as u64), Some(7));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn concurrent_writers_never_mix_up_positions() {
+        use super::{ConcurrentTranspositionTable, ScoreCache};
+
+        // Few buckets, so the threads keep overwriting each others entries.
+        let capacity = 7;
+        let table = ConcurrentTranspositionTable::new(capacity);
+        // Score of each key, derived from the key itself
+        let score = |key: u64| (key % 100) as i8;
+        std::thread::scope(|scope| {
+            for thread in 0..4 {
+                let mut table = &table;
+                scope.spawn(move || {
+                    for key in (1..10_000).filter(|key| key % 4 == thread) {
+                        table.put(key, score(key), 10);
+                        for other in key.saturating_sub(20)..key {
+                            if let Some(found) = table.get(other) {
+                                assert_eq!(score(other), found);
+                            }
+                        }
+                    }
+                });
+            }
+        });
+    }
 }
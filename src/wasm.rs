@@ -0,0 +1,116 @@
+//! Bindings for using the solver from JavaScript via `wasm-bindgen`. Only available with the `wasm`
+//! feature.
+//!
+//! Build a WebAssembly module with
+//!
+//! ```shell
+//! cargo rustc --lib --release --target wasm32-unknown-unknown --features wasm --crate-type cdylib
+//! wasm-bindgen --target web --out-dir pkg target/wasm32-unknown-unknown/release/connect_four_solver.wasm
+//! ```
+
+use wasm_bindgen::prelude::*;
+
+use crate::{Column, ConnectFour, Player, Solver};
+
+/// A connect four board.
+#[wasm_bindgen(js_name = ConnectFour)]
+pub struct WasmConnectFour {
+    game: ConnectFour,
+}
+
+#[wasm_bindgen(js_class = ConnectFour)]
+impl WasmConnectFour {
+    /// Create an empty board
+    #[wasm_bindgen(constructor)]
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> WasmConnectFour {
+        WasmConnectFour {
+            game: ConnectFour::new(),
+        }
+    }
+
+    /// Create a board from a sequence of moves, e.g. `"4453"`. Each move is a digit from 1 to 7.
+    /// Throws if the move list contains invalid or illegal moves, or moves after the game is over.
+    #[wasm_bindgen(js_name = fromMoveList)]
+    pub fn from_move_list(move_list: &str) -> Result<WasmConnectFour, JsError> {
+        let mut game = ConnectFour::new();
+        for c in move_list.chars() {
+            let column: Column = c.to_string().parse().map_err(JsError::new)?;
+            game.try_play(column)
+                .map_err(|error| JsError::new(&format!("Illegal move in move list. {error}")))?;
+        }
+        Ok(WasmConnectFour { game })
+    }
+
+    /// Inserts a stone for the current player into the column with the index `column` (`0` to
+    /// `6`). `true` if the move has been legal.
+    pub fn play(&mut self, column: u8) -> bool {
+        column < 7 && !self.game.is_over() && self.game.play(Column::from_index(column))
+    }
+
+    /// `true` if game has a winner or is a draw.
+    #[wasm_bindgen(js_name = isOver)]
+    pub fn is_over(&self) -> bool {
+        self.game.is_over()
+    }
+
+    /// `1` or `2` for the player who has won, `undefined` if nobody has won (yet).
+    pub fn winner(&self) -> Option<u8> {
+        self.game.winner().map(|player| match player {
+            Player::One => 1,
+            Player::Two => 2,
+        })
+    }
+
+    /// Number of stones in the board
+    pub fn stones(&self) -> u8 {
+        self.game.stones()
+    }
+
+    /// Text representation of the board
+    #[wasm_bindgen(js_name = toString)]
+    pub fn to_string_js(&self) -> String {
+        self.game.to_string()
+    }
+}
+
+/// Solver with a transposition table small enough for the browser.
+#[wasm_bindgen(js_name = Solver)]
+pub struct WasmSolver {
+    solver: Solver,
+}
+
+#[wasm_bindgen(js_class = Solver)]
+impl WasmSolver {
+    /// Creates a solver with a transposition table of about 16MiB, rather than the 64MiB of the
+    /// native default.
+    #[wasm_bindgen(constructor)]
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> WasmSolver {
+        // Prime close to 2^20. 16 Byte per bucket.
+        WasmSolver {
+            solver: Solver::with_capacity(1048573),
+        }
+    }
+
+    /// Score of the board. See `Solver::score`.
+    pub fn score(&mut self, board: &WasmConnectFour) -> i8 {
+        self.solver.score(&board.game)
+    }
+
+    /// JSON array with seven entries, one for each column. Each entry is the score of playing in
+    /// that column from the perspective of the current player, or `null` if the move is illegal.
+    /// See `Solver::analyze`.
+    pub fn analyze(&mut self, board: &WasmConnectFour) -> String {
+        let scores: Vec<String> = self
+            .solver
+            .analyze(&board.game)
+            .iter()
+            .map(|score| match score {
+                Some(score) => score.to_string(),
+                None => "null".to_string(),
+            })
+            .collect();
+        format!("[{}]", scores.join(","))
+    }
+}
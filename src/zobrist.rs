@@ -0,0 +1,49 @@
+use crate::{Cell, ConnectFour, TOTAL_CELLS};
+
+/// Seed of the random values in [`KEYS`]. Changing it changes every hash.
+const SEED: u64 = 0x636f_6e6e_6563_7434;
+
+/// One random value for each cell of the standard board and each player, indexed by
+/// `[column * 6 + row][player]`. Generated at compile time, so they are the same for every build.
+static KEYS: [[u64; 2]; TOTAL_CELLS as usize] = keys();
+
+const fn keys() -> [[u64; 2]; TOTAL_CELLS as usize] {
+    let mut keys = [[0; 2]; TOTAL_CELLS as usize];
+    let mut state = SEED;
+    let mut index = 0;
+    while index < TOTAL_CELLS as usize * 2 {
+        // SplitMix64, whose outputs are well distributed even for similar states.
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut value = state;
+        value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        keys[index / 2][index % 2] = value ^ (value >> 31);
+        index += 1;
+    }
+    keys
+}
+
+impl ConnectFour {
+    /// Zobrist hash of the board: The random values of the cells taken by player one and two
+    /// combined with xor. Unlike [`Self::encode`], which is dense and yields similar numbers for
+    /// similar boards, the bits are evenly distributed, which suits hash tables and other
+    /// structures relying on the bits of a key. Only depends on which player owns which cell, so
+    /// the empty board hashes to `0`.
+    ///
+    /// The random values are fixed and will stay the same in future versions, so the hash may key
+    /// persisted data. Different boards may share a hash, though this is unlikely.
+    pub fn zobrist(&self) -> u64 {
+        let mut hash = 0;
+        for column in 0..7 {
+            for row in 0..6 {
+                let player = match self.cell(row, column) {
+                    Cell::Empty => break,
+                    Cell::PlayerOne => 0,
+                    Cell::PlayerTwo => 1,
+                };
+                hash ^= KEYS[column as usize * 6 + row as usize][player];
+            }
+        }
+        hash
+    }
+}
@@ -1,4 +1,9 @@
-use connect_four_solver::{Column, ConnectFour, Solver};
+use std::{env, fs, sync::atomic::AtomicBool};
+
+use connect_four_solver::{
+    interpret_score, read_dataset, Column, ConnectFour, Difficulty, GameStatus, Player, Rng,
+    ScoreSource, Solver, SolverBuilder, TieBreak, XorShift,
+};
 
 #[test]
 fn pick_best_move() {
@@ -8,4 +13,608 @@ fn pick_best_move() {
     solver.best_moves(&game, &mut best_moves);
 
     assert_eq!(&[Column::from_index(3)][..], &best_moves);
-}
\ No newline at end of file
+}
+
+#[test]
+fn analyze_empty_board() {
+    let mut solver = Solver::new();
+    let game = ConnectFour::new();
+
+    let scores = solver.analyze(&game);
+
+    assert_eq!(
+        [Some(-2), Some(-1), Some(0), Some(1), Some(0), Some(-1), Some(-2)],
+        scores
+    );
+}
+
+#[test]
+fn analyze_full_column() {
+    let mut solver = Solver::new();
+    // Only the two rightmost columns are not full
+    let game = ConnectFour::from_move_list("2252576253462244111563365343671351441");
+
+    let scores = solver.analyze(&game);
+
+    assert_eq!([None, None, None, None, None, Some(-1), Some(-2)], scores);
+}
+
+#[test]
+fn rank_moves_best_first() {
+    let mut solver = Solver::new();
+    let column = |index| Column::from_index(index);
+
+    // Equally good moves are ordered from the center outwards.
+    let ranked = solver.ranked_moves(&ConnectFour::new());
+    let expected = vec![
+        (column(3), 1),
+        (column(4), 0),
+        (column(2), 0),
+        (column(5), -1),
+        (column(1), -1),
+        (column(6), -2),
+        (column(0), -2),
+    ];
+    assert_eq!(expected, ranked);
+
+    // Only the two rightmost columns are not full
+    let game = ConnectFour::from_move_list("2252576253462244111563365343671351441");
+    let ranked = solver.ranked_moves(&game);
+    assert_eq!(vec![(column(5), -1), (column(6), -2)], ranked);
+
+    let game = ConnectFour::from_move_list("4455667");
+    assert!(solver.ranked_moves(&game).is_empty());
+}
+
+#[test]
+fn render_analysis_of_full_column() {
+    let mut solver = Solver::new();
+    let game = ConnectFour::from_move_list("2252576253462244111563365343671351441");
+    let mut out = Vec::new();
+
+    solver.render_analysis(&game, &mut out).unwrap();
+
+    let expected = "\
+        |X  |O  |O  |O  |X  |   |   |\n\
+        |O  |X  |O  |X  |X  |X  |   |\n\
+        |X  |O  |O  |X  |O  |O  |   |\n\
+        |X  |O  |X  |O  |X  |X  |   |\n\
+        |O  |O  |O  |X  |X  |O  |O  |\n\
+        |X  |X  |O  |X  |X  |X  |O  |\n\
+        -----------------------------\n \
+        1   2   3   4   5   6   7\n \
+        -   -   -   -   -   -1  -2\n";
+    assert_eq!(expected, String::from_utf8(out).unwrap());
+}
+
+#[test]
+fn score_without_precalculated_scores() {
+    let mut solver = Solver::new();
+    let game = ConnectFour::from_move_list("4455");
+
+    assert_eq!(solver.score(&game), solver.score_no_book(&game));
+    assert_ne!(0, solver.nodes());
+}
+
+/// The empty board shares its key with empty slots of the transposition table and used to be scored
+/// as a draw. Takes minutes in release builds. Run with `cargo test --release -- --ignored`.
+#[test]
+#[ignore = "takes minutes"]
+fn score_empty_board_without_precalculated_scores() {
+    let mut solver = Solver::new();
+
+    assert_eq!(1, solver.score_no_book(&ConnectFour::new()));
+}
+
+#[test]
+fn solver_without_book_searches_every_position() {
+    let mut solver = Solver::without_book();
+    let game = ConnectFour::from_move_list("4455");
+
+    assert_eq!(Solver::new().score(&game), solver.score(&game));
+    assert_ne!(0, solver.nodes());
+}
+
+/// Same as `score_empty_board_without_precalculated_scores`, but for a solver ignoring them from the
+/// start. Run with `cargo test --release -- --ignored`.
+#[test]
+#[ignore = "takes minutes"]
+fn solver_without_book_scores_empty_board() {
+    let mut solver = Solver::without_book();
+
+    assert_eq!(1, solver.score(&ConnectFour::new()));
+}
+
+#[test]
+fn forced_draw() {
+    let mut solver = Solver::new();
+
+    assert!(solver.is_forced_draw(&ConnectFour::from_move_list(
+        "52677675164321472411331752454"
+    )));
+    assert!(!solver.is_forced_draw(&ConnectFour::new()));
+    assert!(!solver.is_forced_draw(&ConnectFour::from_move_list("44553")));
+    // Full board without a winner
+    assert!(solver.is_forced_draw(&ConnectFour::from_move_list(
+        "547125662261271266215743771576315353334444"
+    )));
+}
+
+#[test]
+fn best_replies_mirror_analyze() {
+    let mut solver = Solver::new();
+    let game = ConnectFour::from_move_list("2252576253462244111563365343671351441");
+
+    let replies = solver.best_replies(&game);
+
+    let expected = [(Column::from_index(5), 1), (Column::from_index(6), 2)];
+    assert_eq!(expected.as_slice(), replies);
+    assert!(solver.best_replies(&ConnectFour::from_move_list("4455667")).is_empty());
+}
+
+#[test]
+fn move_sharpness() {
+    let mut solver = Solver::new();
+
+    assert_eq!(Some(1), solver.move_sharpness(&ConnectFour::new()));
+    // Winning on either end of the open three is equally good.
+    assert_eq!(Some(0), solver.move_sharpness(&ConnectFour::from_move_list("445566")));
+    // Only the column in the middle is not full
+    let game = ConnectFour::from_move_list("54712566226127126621574377157631535333444");
+    assert_eq!(None, solver.move_sharpness(&game));
+    assert_eq!(None, solver.move_sharpness(&ConnectFour::from_move_list("4455667")));
+}
+
+#[test]
+fn solve_yields_principal_variation() {
+    let mut solver = Solver::new();
+    let game = ConnectFour::from_move_list("5554224333234511764415115");
+
+    let solution = solver.solve(&game);
+
+    assert_eq!(solver.score(&game), solution.score);
+    let mut best_moves = Vec::new();
+    solver.best_moves(&game, &mut best_moves);
+    assert_eq!(best_moves.first().copied(), solution.best_move);
+    // Playing the principal variation ends the game after the predicted number of stones, and keeps
+    // the score of each position.
+    let (_outcome, stones_to_end) = interpret_score(&game, solution.score);
+    assert_eq!(stones_to_end as usize, solution.principal_variation.len());
+    let mut position = game;
+    let mut score = solution.score;
+    for &column in &solution.principal_variation {
+        assert_eq!(score, solver.score(&position));
+        position.play(column);
+        score = -score;
+    }
+    assert!(position.is_over());
+}
+
+#[test]
+fn forced_win_line_against_best_defense() {
+    let mut solver = Solver::new();
+    let game = ConnectFour::from_move_list("5554224333234511764415115");
+
+    let line = solver.forced_win_line(&game).unwrap();
+
+    let (_outcome, stones_to_end) = interpret_score(&game, solver.score(&game));
+    assert_eq!(stones_to_end as usize, line.len());
+    let mut position = game;
+    for (index, &column) in line.iter().enumerate() {
+        if index % 2 == 1 {
+            // No other reply of the opponent delays the defeat any longer.
+            let best_defense = solver.analyze(&position).into_iter().flatten().max().unwrap();
+            let mut child = position;
+            child.play(column);
+            assert_eq!(best_defense, -solver.score(&child));
+        }
+        position.play(column);
+    }
+    assert!(position.is_victory());
+    assert_eq!(line.len() % 2, 1);
+}
+
+#[test]
+fn no_forced_win_line_for_drawn_or_lost_positions() {
+    let mut solver = Solver::new();
+
+    let drawn = ConnectFour::from_move_list("52677675164321472411331752454");
+    assert_eq!(None, solver.forced_win_line(&drawn));
+    let lost = ConnectFour::from_move_list("2252576253462244111563365343671351441");
+    assert_eq!(None, solver.forced_win_line(&lost));
+}
+
+#[test]
+fn solve_finished_game() {
+    let mut solver = Solver::new();
+    let game = ConnectFour::from_move_list("12121232");
+
+    let solution = solver.solve(&game);
+
+    assert_eq!(None, solution.best_move);
+    assert!(solution.principal_variation.is_empty());
+}
+
+#[test]
+fn cancelled_search_can_be_resumed() {
+    let mut solver = Solver::new();
+    let game = ConnectFour::from_move_list("5554224333234511764415115");
+
+    assert_eq!(None, solver.score_cancellable(&game, &AtomicBool::new(true)));
+    assert_eq!(Some(4), solver.score_cancellable(&game, &AtomicBool::new(false)));
+}
+
+#[test]
+fn cancelling_does_not_affect_precalculated_scores() {
+    let mut solver = Solver::new();
+    let game = ConnectFour::from_move_list("111111");
+
+    assert_eq!(Some(1), solver.score_cancellable(&game, &AtomicBool::new(true)));
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn parallel_best_moves_match_serial() {
+    let mut solver = Solver::new();
+    for entry in read_dataset("./tests/Test_L3_R1").unwrap().take(50) {
+        let (_, game, _) = entry.unwrap();
+
+        let mut serial = Vec::new();
+        solver.best_moves(&game, &mut serial);
+        let mut parallel = Vec::new();
+        solver.best_moves_parallel(&game, &mut parallel);
+
+        assert_eq!(serial, parallel);
+    }
+}
+
+#[test]
+fn parity_outcome_agrees_with_search() {
+    let solver = Solver::new();
+    let mut predicted = 0;
+    let test_sets = [
+        "./tests/Test_L3_R1",
+        "./tests/Test_L2_R1",
+        "./tests/Test_L2_R2",
+        "./tests/Test_L1_R1",
+        "./tests/Test_L1_R2",
+    ];
+    // Scores of the test sets have been confirmed by the search.
+    for test_set in test_sets {
+        for entry in read_dataset(test_set).unwrap() {
+            let (moves, game, score) = entry.unwrap();
+            if let Some(winner) = solver.parity_outcome(&game) {
+                // The player who moved last wins.
+                assert!(score < 0, "{moves}");
+                assert_eq!(Player::Two, winner);
+                predicted += 1;
+            }
+        }
+    }
+    assert!(predicted > 0);
+}
+
+#[test]
+fn search_effort_does_not_depend_on_previous_searches() {
+    let mut solver = Solver::new();
+    let easy = ConnectFour::from_move_list("2252576253462244111563365343671351441");
+    let hard = ConnectFour::from_move_list("4453");
+
+    let effort = solver.search_effort(&hard);
+    assert!(effort > solver.search_effort(&easy));
+    // Scored before, but the table is cleared, so the search starts from scratch.
+    assert_eq!(effort, solver.search_effort(&hard));
+    // A position which is won right away takes no search at all.
+    assert_eq!(0, solver.search_effort(&ConnectFour::from_move_list("445566")));
+}
+
+#[test]
+fn status_matches_sign_of_score() {
+    let mut solver = Solver::new();
+    let test_sets = ["./tests/Test_L3_R1", "./tests/Test_L2_R1"];
+    for test_set in test_sets {
+        for entry in read_dataset(test_set).unwrap().take(100) {
+            let (moves, game, score) = entry.unwrap();
+            let expected = match score.signum() {
+                1 => GameStatus::CurrentPlayerWins,
+                0 => GameStatus::Drawn,
+                _ => GameStatus::CurrentPlayerLoses,
+            };
+            assert_eq!(expected, solver.status(&game), "{moves}");
+        }
+    }
+    // Won by player one, so player two is to move and has lost.
+    let game = ConnectFour::from_move_list("4455667");
+    assert_eq!(GameStatus::CurrentPlayerLoses, solver.status(&game));
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn shared_table_best_moves_match_serial() {
+    use connect_four_solver::ConcurrentTranspositionTable;
+
+    let mut solver = Solver::new();
+    let table = ConcurrentTranspositionTable::new(1 << 20);
+    for test_set in ["./tests/Test_L3_R1", "./tests/Test_L2_R1"] {
+        for entry in read_dataset(test_set).unwrap().take(50) {
+            let (moves, game, _) = entry.unwrap();
+
+            let mut serial = Vec::new();
+            solver.best_moves(&game, &mut serial);
+            // The table is reused, so later positions find the entries of earlier ones.
+            let mut shared = Vec::new();
+            solver.best_moves_shared(&game, &table, &mut shared);
+
+            assert_eq!(serial, shared, "{moves}");
+        }
+    }
+}
+
+#[test]
+fn depth_limited_score_only_sees_wins_within_depth() {
+    let mut solver = Solver::new();
+    // Player one can win with the third stone from now, by extending the two in the bottom row to
+    // an open three.
+    let game = ConnectFour::from_move_list("4455");
+
+    assert_eq!(0, solver.score_limited(&game, 2));
+    assert_eq!(18, solver.score_limited(&game, 3));
+    assert_eq!(18, solver.score_limited(&game, 5));
+}
+
+#[test]
+fn suggested_moves_depend_on_difficulty() {
+    let mut solver = Solver::new();
+    let mut rng = XorShift::new(42);
+    // Player one wins by playing the third or the sixth column.
+    let game = ConnectFour::from_move_list("4455");
+    let winning = [Column::from_index(2), Column::from_index(5)];
+
+    let suggestion = solver.suggest_move(&game, Difficulty::Hard, &mut rng).unwrap();
+    assert!(winning.contains(&suggestion));
+    let suggestion = solver.suggest_move(&game, Difficulty::Perfect, &mut rng);
+    assert_eq!(Some(Column::from_index(2)), suggestion);
+    // Finished games have no moves to suggest.
+    let game = ConnectFour::from_move_list("4455667");
+    assert_eq!(None, solver.suggest_move(&game, Difficulty::Easy, &mut rng));
+}
+
+#[test]
+fn suggested_moves_are_reproducible() {
+    let mut solver = Solver::new();
+    let play = |solver: &mut Solver, seed| {
+        let mut rng = XorShift::new(seed);
+        let mut game = ConnectFour::new();
+        let mut moves = Vec::new();
+        while let Some(column) = solver.suggest_move(&game, Difficulty::Easy, &mut rng) {
+            game.play(column);
+            moves.push(column);
+        }
+        moves
+    };
+
+    assert_eq!(play(&mut solver, 7), play(&mut solver, 7));
+    assert_ne!(play(&mut solver, 7), play(&mut solver, 8));
+}
+
+#[test]
+fn xor_shift_is_seedable() {
+    let mut rng = XorShift::new(1);
+    let first = rng.next_u64();
+
+    assert_eq!(first, XorShift::new(1).next_u64());
+    assert_ne!(first, rng.next_u64());
+}
+
+#[test]
+fn random_best_move_picks_among_ties() {
+    let mut solver = Solver::new();
+    // Player one wins equally fast by playing the third or the sixth column.
+    let game = ConnectFour::from_move_list("4455");
+    let mut best_moves = Vec::new();
+    solver.best_moves(&game, &mut best_moves);
+    assert_eq!(vec![Column::from_index(2), Column::from_index(5)], best_moves);
+
+    let mut rng = XorShift::new(1);
+    let picked: Vec<Column> = (0..20)
+        .map(|_| solver.best_move_rng(&game, &mut rng).unwrap())
+        .collect();
+
+    assert!(picked.iter().all(|column| best_moves.contains(column)));
+    assert!(picked.contains(&best_moves[0]));
+    assert!(picked.contains(&best_moves[1]));
+}
+
+#[test]
+fn order_ties_among_best_moves() {
+    let mut solver = Solver::new();
+    // Five moves draw, the other two lose.
+    let game = ConnectFour::from_move_list("52677675164321472411331752454");
+    let columns = |indices: &[u8]| -> Vec<Column> {
+        indices.iter().copied().map(Column::from_index).collect()
+    };
+    let mut best_moves = Vec::new();
+
+    solver.best_moves_ordered(&game, TieBreak::Ascending, &mut best_moves);
+    assert_eq!(columns(&[1, 2, 3, 5, 6]), best_moves);
+
+    solver.best_moves_ordered(&game, TieBreak::CenterFirst, &mut best_moves);
+    assert_eq!(columns(&[3, 2, 5, 1, 6]), best_moves);
+
+    solver.best_moves_ordered(&game, TieBreak::Random(42), &mut best_moves);
+    let mut shuffled = best_moves.clone();
+    solver.best_moves_ordered(&game, TieBreak::Random(42), &mut shuffled);
+    assert_eq!(best_moves, shuffled);
+    shuffled.sort_by_key(|column| column.to_string());
+    assert_eq!(columns(&[1, 2, 3, 5, 6]), shuffled);
+}
+
+#[test]
+fn seeded_solver_replays_games() {
+    let play_out = |mut solver: Solver| {
+        // Five moves draw, so there are plenty of ties to break.
+        let mut game = ConnectFour::from_move_list("52677675164321472411331752454");
+        let mut moves = Vec::new();
+        while let Some(column) = solver.best_move(&game) {
+            game.play(column);
+            moves.push(column);
+        }
+        moves
+    };
+
+    assert_eq!(play_out(Solver::seeded(42)), play_out(Solver::seeded(42)));
+    // Without a seed the leftmost of the best moves is played.
+    let game = ConnectFour::from_move_list("4455");
+    assert_eq!(Some(Column::from_index(2)), Solver::new().best_move(&game));
+}
+
+#[test]
+fn source_of_scores() {
+    let mut solver = Solver::new();
+    assert_eq!(
+        (1, ScoreSource::Book),
+        solver.score_source(&ConnectFour::new())
+    );
+
+    // Lost as fast as possible, after the second stone of the opponent.
+    let game = ConnectFour::from_move_list("26512741647245111351472255277");
+    assert_eq!((-5, ScoreSource::Searched), solver.score_source(&game));
+    assert_eq!((-5, ScoreSource::TableExact), solver.score_source(&game));
+
+    let game = ConnectFour::from_move_list("52677675164321472411331752454");
+    assert_eq!((0, ScoreSource::Searched), solver.score_source(&game));
+    assert_eq!((0, ScoreSource::TableBound), solver.score_source(&game));
+}
+
+#[test]
+fn configure_solver_with_builder() {
+    let game = ConnectFour::from_move_list("4455");
+
+    let mut solver = SolverBuilder::new()
+        .table_entries(1 << 16)
+        .use_book(false)
+        .build();
+    assert_eq!(Solver::new().score(&game), solver.score(&game));
+    assert_ne!(0, solver.nodes());
+
+    // Player one wins equally fast by playing the third or the sixth column.
+    let mut solver = SolverBuilder::new()
+        .tie_break(TieBreak::CenterFirst)
+        .build();
+    assert_eq!(Some(Column::from_index(2)), solver.best_move(&game));
+    let game = ConnectFour::from_move_list("52677675164321472411331752454");
+    assert_eq!(Some(Column::from_index(3)), solver.best_move(&game));
+
+    let play = |mut solver: Solver| solver.best_move(&game);
+    assert_eq!(
+        play(Solver::seeded(7)),
+        play(SolverBuilder::new().seed(7).build())
+    );
+}
+
+#[test]
+fn plies_to_end_of_drawn_game_fill_the_board() {
+    let mut solver = Solver::new();
+    let game = ConnectFour::from_move_list("52677675164321472411331752454");
+
+    assert_eq!(42 - 29, solver.plies_to_end(&game));
+}
+
+#[test]
+fn plies_to_end_of_forced_wins_and_losses() {
+    let mut solver = Solver::new();
+
+    // Win with the next stone
+    assert_eq!(1, solver.plies_to_end(&ConnectFour::from_move_list("445566")));
+    // Open three with the next stone, win with the third
+    assert_eq!(3, solver.plies_to_end(&ConnectFour::from_move_list("4455")));
+    // Player two can only block one side of the open three
+    assert_eq!(2, solver.plies_to_end(&ConnectFour::from_move_list("44553")));
+    // Game is already over
+    assert_eq!(0, solver.plies_to_end(&ConnectFour::from_move_list("4455667")));
+}
+
+#[test]
+fn empty_board_is_won_by_first_player() {
+    let mut solver = Solver::new();
+    let game = ConnectFour::new();
+
+    // The precalculated score of the empty board used to be zero, i.e. a draw.
+    assert_eq!(1, solver.score(&game));
+    assert_eq!(41, solver.plies_to_end(&game));
+}
+
+#[test]
+fn preloaded_scores_save_search() {
+    let game = ConnectFour::from_move_list("1233722555341451114725221333");
+    let mut cold = Solver::new();
+    let mut preloaded = Solver::new();
+
+    preloaded.preload(&[(game.encode(), -1)]);
+
+    assert_eq!(-1, cold.score(&game));
+    assert_eq!(-1, preloaded.score(&game));
+    assert!(preloaded.nodes() < cold.nodes());
+}
+
+#[test]
+fn preload_empty_board() {
+    let game = ConnectFour::new();
+    let mut solver = Solver::without_book();
+
+    solver.preload(&[(game.encode(), 1)]);
+
+    assert_eq!(Some(1), solver.cached_bound(&game));
+}
+
+#[test]
+fn cached_bound_is_upper_bound_of_score() {
+    let mut solver = Solver::new();
+    let game = ConnectFour::from_move_list("1233722555341451114725221333");
+    assert_eq!(None, solver.cached_bound(&game));
+
+    let score = solver.score(&game);
+
+    let bound = solver.cached_bound(&game).unwrap();
+    assert!(score <= bound);
+}
+
+#[test]
+fn empty_board_is_not_cached_unless_searched() {
+    let mut solver = Solver::without_book();
+    let game = ConnectFour::new();
+    assert_eq!(None, solver.cached_bound(&game));
+
+    // Fills the table, but never with the empty board.
+    solver.score(&ConnectFour::from_move_list("4455"));
+
+    assert_eq!(None, solver.cached_bound(&game));
+}
+
+#[test]
+fn mirror_image_shares_canonical_key() {
+    let mut solver = Solver::new();
+    solver.set_canonical_keys(true);
+    let game = ConnectFour::from_move_list("1233722555341451114725221333");
+
+    let score = solver.score(&game);
+
+    let bound = solver.cached_bound(&game.mirror()).unwrap();
+    assert!(score <= bound);
+    assert_eq!(score, solver.score(&game.mirror()));
+}
+
+#[test]
+fn reuse_saved_transposition_table() {
+    let game = ConnectFour::from_move_list("1233722555341451114725221333");
+    let path = env::temp_dir().join("connect_four_solver_reuse_saved_table.bin");
+    let mut solver = Solver::with_capacity(8191);
+    let score = solver.score(&game);
+
+    solver.save_table(&path).unwrap();
+    let mut loaded = Solver::load_table(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(score, loaded.score(&game));
+    assert!(loaded.nodes() < solver.nodes());
+}
@@ -1,9 +1,16 @@
 use std::{
-    fs::File,
-    io::{BufRead, BufReader},
+    collections::{hash_map::DefaultHasher, HashMap},
+    env, fs,
+    hash::{Hash, Hasher},
+    io,
 };
 
-use connect_four_solver::{score, ConnectFour};
+use connect_four_solver::{
+    count_positions, interpret_score, precalculated_depth, read_dataset, score, verify_dataset,
+    Board, BuildError, CanonicalBoard, CellMap, Column, ConnectFour, ConnectFourBuilder,
+    DecodeError, GameResult, NotationError, NotationFormat, Outcome, Phase, PlayError, PlayedGame,
+    Player, PositionSet, RenderStyle, Rng, Solver, TreeVisitor, Visit, XorShift, TOTAL_CELLS,
+};
 
 /// Construct game state from move list and print it correctly.
 #[test]
@@ -26,6 +33,57 @@ fn construct_from_move_list() {
     assert_eq!(expected, out);
 }
 
+#[test]
+fn reset_board_equals_new_board() {
+    let mut game = ConnectFour::from_move_list("5655663642443");
+
+    game.reset();
+
+    assert!(game == ConnectFour::new());
+}
+
+#[test]
+fn play_out_until_victory() {
+    let mut game = ConnectFour::new();
+
+    // Always picking the leftmost column fills the bottom row from the left, so player one
+    // completes a horizontal line with the 19th stone.
+    let result = game.play_out(|game| game.legal_moves().next().unwrap());
+
+    assert_eq!(Ok(GameResult::Victory(Player::One)), result);
+    assert_eq!(19, game.stones());
+}
+
+#[test]
+fn play_out_until_draw() {
+    let moves = "656173566152215676422337377473141445425321";
+    let mut game = ConnectFour::new();
+
+    let mut moves = moves.chars().map(|c| c.to_string().parse::<Column>().unwrap());
+    let result = game.play_out(|_| moves.next().unwrap());
+
+    assert_eq!(Ok(GameResult::Draw), result);
+    assert_eq!(0, game.remaining_moves());
+}
+
+#[test]
+fn remaining_moves() {
+    assert_eq!(TOTAL_CELLS, ConnectFour::new().remaining_moves());
+    assert_eq!(38, ConnectFour::from_move_list("4453").remaining_moves());
+    assert_eq!(20, Board::<5, 4>::new().remaining_moves());
+}
+
+#[test]
+fn play_out_rejects_illegal_move() {
+    let mut game = ConnectFour::new();
+
+    // Column is full after six stones
+    let result = game.play_out(|_| Column::from_index(0));
+
+    assert_eq!(Err(Column::from_index(0)), result);
+    assert_eq!(6, game.stones());
+}
+
 #[test]
 fn should_detect_win_of_player_one() {
     // | | | | | | | |
@@ -41,6 +99,1165 @@ fn should_detect_win_of_player_one() {
     assert!(game.is_victory());
 }
 
+#[test]
+fn render_unicode() {
+    let game = ConnectFour::from_move_list("5655663642443");
+    let mut out = Vec::new();
+    game.render(RenderStyle::Unicode, &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+
+    let expected = "\
+        │ │ │ │ │ │ │ │\n\
+        │ │ │ │ │ │ │ │\n\
+        │ │ │ │ │ │○│ │\n\
+        │ │ │ │○│○│○│ │\n\
+        │ │ │●│●│●│●│ │\n\
+        │ │○│●│●│●│○│ │\n\
+        └─┴─┴─┴─┴─┴─┴─┘\n \
+         1 2 3 4 5 6 7\n\
+    ";
+    assert_eq!(expected, out);
+}
+
+#[test]
+fn descendants_of_position() {
+    let game = ConnectFour::from_move_list("44");
+
+    // Seven moves for each player. Exchanging the moves would exchange the owners of the stones,
+    // so no two of them lead to the same position.
+    let descendants = game.descendants(2, false);
+    assert_eq!(49, descendants.len());
+    assert!(descendants.iter().all(|board| board.stones() == 4));
+    assert!(descendants.windows(2).all(|pair| pair[0].encode() < pair[1].encode()));
+    // The position is symmetric, so most descendants have a mirror image among them.
+    assert!(game.descendants(2, true).len() < descendants.len());
+    // Games which are over have no descendants
+    assert!(ConnectFour::from_move_list("4455667").descendants(1, false).is_empty());
+}
+
+#[test]
+fn count_lines_of_play() {
+    let game = ConnectFour::new();
+    assert_eq!(1, game.count_lines(0));
+    assert_eq!(7, game.count_lines(1));
+    assert_eq!(2401, game.count_lines(4));
+    // Seven sequences fill a column with the first six stones, leaving six moves for the seventh.
+    assert_eq!(7u64.pow(7) - 7, game.count_lines(7));
+
+    // Two of the moves of player one win, and end the line early.
+    assert_eq!(5 * 7 + 2, ConnectFour::from_move_list("445566").count_lines(2));
+    assert_eq!(1, ConnectFour::from_move_list("4455667").count_lines(3));
+}
+
+#[test]
+fn walk_tree() {
+    /// Records entering and leaving nodes, skipping everything below the first move into the
+    /// leftmost column.
+    #[derive(Default)]
+    struct Recorder {
+        events: Vec<(bool, u8, u64)>,
+    }
+
+    impl TreeVisitor for Recorder {
+        fn enter(&mut self, game: &ConnectFour, depth: u8) -> Visit {
+            self.events.push((true, depth, game.encode()));
+            if *game == ConnectFour::from_move_list("1") {
+                Visit::SkipChildren
+            } else {
+                Visit::Children
+            }
+        }
+
+        fn leave(&mut self, game: &ConnectFour, depth: u8) {
+            self.events.push((false, depth, game.encode()));
+        }
+    }
+
+    let mut recorder = Recorder::default();
+    ConnectFour::new().walk_tree(&mut recorder, 2);
+
+    // One root, seven children and 49 grandchildren, but the seven below "1" are skipped.
+    assert_eq!(2 * (1 + 7 + 49 - 7), recorder.events.len());
+    let key = |moves: &str| ConnectFour::from_move_list(moves).encode();
+    assert_eq!(
+        vec![
+            (true, 0, key("")),
+            (true, 1, key("1")),
+            (false, 1, key("1")),
+            (true, 1, key("2")),
+            (true, 2, key("21")),
+            (false, 2, key("21")),
+        ],
+        recorder.events[..6]
+    );
+    assert_eq!((false, 0, key("")), *recorder.events.last().unwrap());
+
+    // Won games are not played on
+    let mut recorder = Recorder::default();
+    ConnectFour::from_move_list("4455667").walk_tree(&mut recorder, 3);
+    assert_eq!(2, recorder.events.len());
+}
+
+#[test]
+fn round_trip_keys() {
+    let mut games = ConnectFour::new().descendants(6, false);
+    for entry in read_dataset("./tests/Test_L2_R1").unwrap() {
+        let (_, game, _) = entry.unwrap();
+        games.push(game);
+    }
+    // Won and drawn games
+    games.push(ConnectFour::from_move_list("4455667"));
+    games.push(ConnectFour::from_move_list(
+        "547125662261271266215743771576315353334444",
+    ));
+    for game in games {
+        assert!(ConnectFour::from_key(game.encode()) == Ok(game));
+        assert!(ConnectFour::from_grid(&game.to_grid()) == Ok(game));
+    }
+}
+
+#[test]
+fn reject_invalid_keys() {
+    let error = |key: u64| ConnectFour::from_key(key).err();
+    // Seven stones in a column
+    assert_eq!(Some(DecodeError::InvalidLayout), error(0b111_1111));
+    // Bits beyond the board
+    assert_eq!(Some(DecodeError::InvalidLayout), error(1 << 60));
+    // Three stones in the first column, none of them put in by the player who moved last.
+    assert_eq!(Some(DecodeError::Unreachable), error(0b111));
+    // Both players have four in a row.
+    let mut both_won = ConnectFourBuilder::new();
+    for row in 0..4 {
+        both_won = both_won
+            .cell(row, 0, Some(Player::One))
+            .cell(row, 1, Some(Player::Two));
+    }
+    let both_won = both_won.build().unwrap().encode();
+    assert_eq!(Some(DecodeError::Unreachable), error(both_won));
+    // Player one completed a four in a row in the first column with the seventh stone, but `play`
+    // does not stop the game.
+    let won_before = ConnectFour::from_move_list("121212113");
+    assert_eq!(Some(DecodeError::Unreachable), error(won_before.encode()));
+    let grid = won_before.to_grid();
+    assert!(ConnectFour::from_grid(&grid) == Err(DecodeError::Unreachable));
+    // Floating stone
+    let mut grid = [[None; 7]; 6];
+    grid[1][0] = Some(Player::One);
+    assert!(ConnectFour::from_grid(&grid) == Err(DecodeError::InvalidLayout));
+    // Won with the last stone
+    let won = ConnectFour::from_move_list("1212121").encode();
+    assert!(ConnectFour::from_key(won).is_ok());
+}
+
+/// Same checks as the fuzz targets in `fuzz/`, for random inputs. The fuzzer requires a nightly
+/// compiler, so this keeps an eye on the parsers in every test run.
+#[test]
+fn parsers_accept_only_legal_games() {
+    let mut rng = XorShift::new(42);
+    let formats = [
+        NotationFormat::Packed1Indexed,
+        NotationFormat::Spaced1Indexed,
+        NotationFormat::Comma0Indexed,
+    ];
+    let alphabet = b"0123456789, \n+-";
+    // Number of accepted notations and grids, to make sure the checks are not vacuous.
+    let (mut notations, mut grids) = (0, 0);
+    for _ in 0..10_000 {
+        let len = rng.next_u64() as usize % 50;
+        let bytes: Vec<u8> = (0..len).map(|_| rng.next_u64() as u8).collect();
+        if let Ok(game) = PlayedGame::from_bytes(&bytes) {
+            let mut replayed = ConnectFour::new();
+            assert!(game.moves().iter().all(|&column| replayed.try_play(column).is_ok()));
+            assert!(replayed == *game.board());
+        }
+        let notation: String = bytes
+            .iter()
+            .map(|&byte| alphabet[byte as usize % alphabet.len()] as char)
+            .collect();
+        for format in formats {
+            if let Ok(game) = ConnectFour::from_notation(&notation, format) {
+                assert!(ConnectFour::from_key(game.encode()) == Ok(game), "{notation}");
+                assert_reachable(&game);
+                notations += 1;
+            }
+        }
+
+        // Mostly full grids would almost always have floating stones, so fill columns from the
+        // bottom and only sometimes leave a gap.
+        let mut builder = ConnectFourBuilder::new();
+        let mut grid = [[None; 7]; 6];
+        for column in 0..7 {
+            let height = rng.next_u64() % 7;
+            for row in 0..6 {
+                let player = match rng.next_u64() % 16 {
+                    _ if row >= height => None,
+                    0 => None,
+                    value if value % 2 == 0 => Some(Player::One),
+                    _ => Some(Player::Two),
+                };
+                grid[row as usize][column as usize] = player;
+                builder = builder.cell(row as u8, column, player);
+            }
+        }
+        // The builder accepts any grid without floating stones, `from_grid` only reachable ones.
+        if let Ok(game) = builder.build() {
+            assert_eq!(grid, game.to_grid());
+        }
+        if let Ok(game) = ConnectFour::from_grid(&grid) {
+            assert_eq!(grid, game.to_grid());
+            assert_reachable(&game);
+            grids += 1;
+        }
+    }
+    assert!(notations > 100 && grids > 100, "{notations} {grids}");
+}
+
+/// Panics unless `game` obeys the rules of a game played from the empty board: One player has put
+/// in as many stones as the other one, or one more. That is player one, unless the players have
+/// been swapped. Every four in a row goes through the topmost stone of a column, which belongs to
+/// the player who moved last. That stone ended the game.
+fn assert_reachable(game: &ConnectFour) {
+    let grid = game.to_grid();
+    let count = |player| grid.iter().flatten().filter(|&&cell| cell == Some(player)).count();
+    let (one, two) = (count(Player::One), count(Player::Two));
+    assert!(one.abs_diff(two) <= 1, "{game}");
+    let lines = game.all_winning_lines();
+    let last_player = if one > two { Player::One } else { Player::Two };
+    let may_be_last_stone = |&(row, column): &(u8, u8)| {
+        let on_top = row == 5 || grid[row as usize + 1][column as usize].is_none();
+        on_top && grid[row as usize][column as usize] == Some(last_player)
+    };
+    if let Some(first) = lines.first() {
+        let last_stone = first
+            .iter()
+            .filter(|cell| may_be_last_stone(cell))
+            .find(|&cell| lines.iter().all(|line| line.contains(cell)));
+        assert!(last_stone.is_some(), "{game}");
+    }
+}
+
+#[test]
+fn zobrist_hash() {
+    // The values are part of the contract, since hashes may be persisted.
+    assert_eq!(0, ConnectFour::new().zobrist());
+    assert_eq!(
+        0xcd49_48b3_8a50_523e,
+        ConnectFour::from_move_list("4453").zobrist()
+    );
+    // Transpositions share the hash
+    assert_eq!(
+        ConnectFour::from_move_list("4453").zobrist(),
+        ConnectFour::from_move_list("5344").zobrist()
+    );
+
+    // No collisions among the first positions, and the low bits spread evenly.
+    let positions: Vec<ConnectFour> = (0..=7)
+        .flat_map(|plies| ConnectFour::new().descendants(plies, false))
+        .collect();
+    let mut hashes: Vec<u64> = positions.iter().map(|game| game.zobrist()).collect();
+    let mut buckets = [0usize; 256];
+    for hash in &hashes {
+        buckets[(hash % 256) as usize] += 1;
+    }
+    let expected = hashes.len() / 256;
+    assert!(buckets.iter().all(|&count| count.abs_diff(expected) < expected / 4));
+    hashes.sort_unstable();
+    hashes.dedup();
+    assert_eq!(positions.len(), hashes.len());
+}
+
+#[test]
+fn position_set_treats_mirror_images_as_duplicates() {
+    let mut positions = PositionSet::new();
+    let game = ConnectFour::from_move_list("1234");
+
+    assert!(positions.insert(&game));
+    assert!(!positions.insert(&game));
+    assert!(!positions.insert(&game.mirror()));
+    assert!(positions.contains(&ConnectFour::from_move_list("7654")));
+    assert!(positions.insert(&ConnectFour::from_move_list("4321")));
+    assert_eq!(2, positions.len());
+    assert!(positions.keys().any(|key| key == game.canonical_encode()));
+}
+
+#[test]
+fn render_marked_columns() {
+    let game = ConnectFour::from_move_list("45");
+    let mut out = Vec::new();
+    let marked = [Column::from_index(1), Column::from_index(3)];
+    game.render_marked(RenderStyle::Ascii, &marked, &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+
+    let mut lines = out.lines().skip(7);
+    assert_eq!(" 1 2 3 4 5 6 7", lines.next().unwrap());
+    assert_eq!("   *   *", lines.next().unwrap());
+    assert_eq!(None, lines.next());
+}
+
+#[test]
+fn render_cell_map() {
+    let game = ConnectFour::from_move_list("45");
+    let mut values = CellMap::from_fn(|row, column| row * 10 + column);
+    assert_eq!(34, values[(3, 4)]);
+    values[(1, 3)] = 7;
+    let mut out = Vec::new();
+    game.render_cell_map(&values, &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+
+    let expected = "\
+        |50|51|52|53|54|55|56|\n\
+        |40|41|42|43|44|45|46|\n\
+        |30|31|32|33|34|35|36|\n\
+        |20|21|22|23|24|25|26|\n\
+        |10|11|12|7 |14|15|16|\n\
+        |0 |1 |2 |X |O |5 |6 |\n\
+        ----------------------\n \
+        1  2  3  4  5  6  7\n";
+    assert_eq!(expected, out);
+}
+
+#[test]
+fn print_with_custom_symbols() {
+    let game = ConnectFour::from_move_list("45");
+    let mut out = Vec::new();
+    game.print_with_symbols('1', '2', '.', &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+
+    let expected = "\
+        |.|.|.|.|.|.|.|\n\
+        |.|.|.|.|.|.|.|\n\
+        |.|.|.|.|.|.|.|\n\
+        |.|.|.|.|.|.|.|\n\
+        |.|.|.|.|.|.|.|\n\
+        |.|.|.|1|2|.|.|\n\
+        ---------------\n \
+         1 2 3 4 5 6 7\n\
+    ";
+    assert_eq!(expected, out);
+}
+
+#[test]
+fn print_with_wide_symbols() {
+    let game = ConnectFour::from_move_list("45");
+    let mut out = Vec::new();
+    game.print_with_symbols('🔴', '🟡', ' ', &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+
+    // Empty cells are padded to the width of the emoji
+    let mut lines = out.lines().skip(5);
+    assert_eq!("|  |  |  |🔴|🟡|  |  |", lines.next().unwrap());
+    assert_eq!("----------------------", lines.next().unwrap());
+    assert_eq!(" 1  2  3  4  5  6  7", lines.next().unwrap());
+}
+
+#[test]
+fn display_matches_print_to() {
+    let game = ConnectFour::from_move_list("5655663642443");
+    let mut printed = Vec::new();
+    game.print_to(&mut printed).unwrap();
+
+    assert_eq!(String::from_utf8(printed).unwrap(), game.to_string());
+}
+
+#[test]
+fn display_on_single_line() {
+    let game = ConnectFour::from_move_list("44453");
+
+    assert_eq!(
+        concat!(".......", ".......", ".......", "...X...", "...O...", "..XXO.."),
+        format!("{game:#}")
+    );
+}
+
+#[test]
+fn render_ansi_color() {
+    let game = ConnectFour::from_move_list("45");
+    let mut out = Vec::new();
+    game.render(RenderStyle::AnsiColor, &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+
+    let bottom_row = out.lines().nth(5).unwrap();
+    assert_eq!("│ │ │ │\x1b[31m●\x1b[0m│\x1b[33m●\x1b[0m│ │ │", bottom_row);
+}
+
+#[test]
+fn render_ascii_is_print_to() {
+    let game = ConnectFour::from_move_list("5655663642443");
+    let mut rendered = Vec::new();
+    game.render(RenderStyle::Ascii, &mut rendered).unwrap();
+    let mut printed = Vec::new();
+    game.print_to(&mut printed).unwrap();
+
+    assert_eq!(printed, rendered);
+}
+
+#[test]
+fn svg_contains_one_circle_per_cell() {
+    let game = ConnectFour::from_move_list("45");
+
+    let svg = game.to_svg();
+
+    assert!(svg.starts_with("<svg"));
+    assert_eq!(42, svg.matches("<circle").count());
+    assert_eq!(1, svg.matches("fill=\"#e02020\"").count());
+    assert_eq!(1, svg.matches("fill=\"#f0d020\"").count());
+    assert_eq!(0, svg.matches("stroke=").count());
+}
+
+#[test]
+fn svg_highlights_cells() {
+    let game = ConnectFour::from_move_list("5655663642443");
+
+    let svg = game.to_svg_highlighted(&[(1, 2), (1, 3), (1, 4), (1, 5)]);
+
+    assert_eq!(42, svg.matches("<circle").count());
+    assert_eq!(4, svg.matches("stroke=").count());
+    // Highlighted stone in the bottom left of the winning line
+    assert!(svg.contains("<circle cx=\"250\" cy=\"450\" r=\"40\" fill=\"#e02020\" stroke="));
+}
+
+#[test]
+fn victories_of_many_games() {
+    // Every position reached while playing the games of the test set, including the won final
+    // positions.
+    let mut games = Vec::new();
+    for entry in read_dataset("./tests/Test_L3_R1").unwrap() {
+        let (_, mut game, _) = entry.unwrap();
+        games.push(game);
+        while !game.is_over() {
+            let column = game.legal_moves().next().unwrap();
+            game.play(column);
+            games.push(game);
+        }
+    }
+
+    let expected: Vec<bool> = games.iter().map(ConnectFour::is_victory).collect();
+    let actual: Vec<bool> = ConnectFour::victories(&games).collect();
+
+    assert!(expected.iter().any(|&win| win));
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn board_as_grid() {
+    let game = ConnectFour::from_move_list("4452");
+
+    let grid = game.to_grid();
+
+    let (one, two) = (Some(Player::One), Some(Player::Two));
+    let mut expected = [[None; 7]; 6];
+    // Row 0 is the bottom row
+    expected[0] = [None, two, None, one, one, None, None];
+    expected[1] = [None, None, None, two, None, None, None];
+    assert_eq!(expected, grid);
+}
+
+#[test]
+fn planes_are_relative_to_side_to_move() {
+    // Player one to move. Player one has stones in columns 4 and 5, player two in 2 and 4.
+    let game = ConnectFour::from_move_list("4452");
+
+    let planes = game.to_planes();
+
+    let mut mine = [[0.; 7]; 6];
+    mine[0][3] = 1.;
+    mine[0][4] = 1.;
+    let mut theirs = [[0.; 7]; 6];
+    theirs[0][1] = 1.;
+    theirs[1][3] = 1.;
+    assert_eq!([mine, theirs], planes);
+
+    // Player two to move, after player one played into column 7.
+    let game = ConnectFour::from_move_list("44527");
+
+    let planes = game.to_planes();
+
+    let mut mine = [[0.; 7]; 6];
+    mine[0][1] = 1.;
+    mine[1][3] = 1.;
+    let mut theirs = [[0.; 7]; 6];
+    theirs[0][3] = 1.;
+    theirs[0][4] = 1.;
+    theirs[0][6] = 1.;
+    assert_eq!([mine, theirs], planes);
+}
+
+#[test]
+fn mirror_board() {
+    let game = ConnectFour::from_move_list("4452");
+    let mirrored = ConnectFour::from_move_list("4436");
+
+    assert!(game.mirror() == mirrored);
+    assert!(mirrored.mirror() == game);
+    assert_ne!(game.encode(), mirrored.encode());
+    assert_eq!(game.canonical_encode(), mirrored.canonical_encode());
+}
+
+#[test]
+fn canonical_boards_treat_mirrors_as_identical() {
+    let game = ConnectFour::from_move_list("4452");
+    let mirrored = ConnectFour::from_move_list("4436");
+    let other = ConnectFour::from_move_list("4453");
+
+    assert!(CanonicalBoard(game) == CanonicalBoard(mirrored));
+    assert!(CanonicalBoard(game) != CanonicalBoard(other));
+
+    let mut scores = HashMap::new();
+    scores.insert(CanonicalBoard(game), 1);
+    scores.insert(CanonicalBoard(mirrored), 2);
+    scores.insert(CanonicalBoard(other), 3);
+    assert_eq!(2, scores.len());
+    assert_eq!(Some(&2), scores.get(&CanonicalBoard(game)));
+}
+
+#[test]
+fn try_play_tells_why_move_is_illegal() {
+    let mut game = ConnectFour::from_move_list("111111");
+    assert_eq!(Err(PlayError::ColumnFull), game.try_play(Column::from_index(0)));
+    assert_eq!(Ok(()), game.try_play(Column::from_index(1)));
+
+    // Player two has won. Column 3 still has room, but the game is over.
+    let mut game = ConnectFour::from_move_list("12121232");
+    assert_eq!(Err(PlayError::GameAlreadyOver), game.try_play(Column::from_index(2)));
+    assert_eq!(8, game.stones());
+}
+
+#[test]
+fn read_moves_in_other_notations() {
+    let expected = ConnectFour::from_move_list("4453");
+    let read = |notation, format| ConnectFour::from_notation(notation, format).unwrap();
+
+    assert!(expected == read("4453", NotationFormat::Packed1Indexed));
+    assert!(expected == read(" 44 53\n", NotationFormat::Packed1Indexed));
+    assert!(expected == read("4  4 5\t3", NotationFormat::Spaced1Indexed));
+    assert!(expected == read("3, 3,4 ,2", NotationFormat::Comma0Indexed));
+    assert!(ConnectFour::new() == read("", NotationFormat::Comma0Indexed));
+}
+
+#[test]
+fn reject_invalid_notation() {
+    let read = |notation, format| ConnectFour::from_notation(notation, format).err().unwrap();
+
+    assert_eq!(
+        NotationError::InvalidToken { index: 2 },
+        read("440", NotationFormat::Packed1Indexed)
+    );
+    assert_eq!(
+        NotationError::InvalidToken { index: 1 },
+        read("4 x", NotationFormat::Spaced1Indexed)
+    );
+    assert_eq!(
+        NotationError::InvalidToken { index: 1 },
+        read("3,,3", NotationFormat::Comma0Indexed)
+    );
+    assert_eq!(
+        NotationError::IllegalMove {
+            index: 1,
+            error: PlayError::NoSuchColumn
+        },
+        read("3,7", NotationFormat::Comma0Indexed)
+    );
+    assert_eq!(
+        NotationError::IllegalMove {
+            index: 6,
+            error: PlayError::ColumnFull
+        },
+        read("1 1 1 1 1 1 1", NotationFormat::Spaced1Indexed)
+    );
+}
+
+#[test]
+fn played_game_to_and_from_bytes() {
+    let record = |moves: &str| -> PlayedGame {
+        let mut game = PlayedGame::new();
+        for c in moves.bytes() {
+            game.try_play(Column::from_index(c - b'1')).unwrap();
+        }
+        game
+    };
+    let moves = |bytes: &[u8]| PlayedGame::from_bytes(bytes).map(|game| game.moves().to_vec());
+    // Drawn game filling the whole board
+    let full = record("547125662261271266215743771576315353334444");
+    let partial = record("4453");
+
+    assert_eq!(16, full.to_bytes().len());
+    assert_eq!(Ok(full.moves().to_vec()), moves(&full.to_bytes()));
+    assert_eq!(2, partial.to_bytes().len());
+    assert_eq!(Ok(partial.moves().to_vec()), moves(&partial.to_bytes()));
+    assert_eq!(Ok(Vec::new()), moves(&PlayedGame::new().to_bytes()));
+
+    // The moves are replayed
+    let decoded = PlayedGame::from_bytes(&partial.to_bytes()).unwrap();
+    assert!(ConnectFour::from_move_list("4453") == *decoded.board());
+}
+
+#[test]
+fn played_game_from_illegal_bytes() {
+    let error = |bytes: &[u8]| PlayedGame::from_bytes(bytes).err();
+    // Seven stones in the first column. Three bits of `0` each, padding in the last three bits.
+    assert_eq!(
+        Some(NotationError::IllegalMove {
+            index: 6,
+            error: PlayError::ColumnFull
+        }),
+        error(&[0x00, 0x00, 0xe0])
+    );
+    // Padding in the first of two bytes
+    assert_eq!(
+        Some(NotationError::InvalidToken { index: 0 }),
+        error(&[0xff, 0x00])
+    );
+    // Five moves leave a single bit of padding at the end of the second byte.
+    let mut game = PlayedGame::new();
+    for column in [0, 1, 0, 1, 0] {
+        game.try_play(Column::from_index(column)).unwrap();
+    }
+    let mut bytes = game.to_bytes();
+    bytes[1] &= 0x7f;
+    assert_eq!(Some(NotationError::InvalidToken { index: 5 }), error(&bytes));
+}
+
+#[test]
+fn play_all_stops_at_first_illegal_move() {
+    let moves: Vec<Column> = [1, 1, 1, 1, 1, 1, 1, 2].map(Column::from_index).into();
+    let mut game = ConnectFour::new();
+
+    let result = game.play_all(&moves);
+
+    assert_eq!(Err((6, PlayError::ColumnFull)), result);
+    // The first six moves have been applied
+    assert!(ConnectFour::from_move_list("222222") == game);
+
+    let mut game = ConnectFour::new();
+    assert_eq!(Ok(()), game.play_all(&moves[..6]));
+    assert!(ConnectFour::from_move_list("222222") == game);
+}
+
+#[test]
+fn replay_game() {
+    let moves: Vec<Column> = [3, 3, 4].map(Column::from_index).into();
+    let mut visited = Vec::new();
+
+    let result = PlayedGame::replay(&moves, |game, column| visited.push((game.stones(), column)));
+
+    let game = result.unwrap();
+    assert!(ConnectFour::from_move_list("445") == *game.board());
+    assert_eq!(moves, game.moves());
+    assert_eq!(vec![(1, moves[0]), (2, moves[1]), (3, moves[2])], visited);
+
+    // Stops at the first illegal move
+    let moves: Vec<Column> = [0, 0, 0, 0, 0, 0, 0].map(Column::from_index).into();
+    let mut visited = 0;
+    let result = PlayedGame::replay(&moves, |_, _| visited += 1);
+    assert_eq!(Some((6, PlayError::ColumnFull)), result.err());
+    assert_eq!(6, visited);
+}
+
+#[test]
+fn replay_decoded_game() {
+    let game = PlayedGame::replay(&[3, 3, 4].map(Column::from_index), |_, _| ()).unwrap();
+    let decoded = PlayedGame::from_bytes(&game.to_bytes()).unwrap();
+    let mut stones = Vec::new();
+
+    let replayed = PlayedGame::replay(decoded.moves(), |board, _| stones.push(board.stones()));
+
+    assert!(replayed.unwrap() == decoded);
+    assert_eq!(vec![1, 2, 3], stones);
+}
+
+#[test]
+fn same_pattern_ignores_colors() {
+    let game = ConnectFour::from_move_list("4452");
+    // Same stones, but player one owns those of player two and vice versa.
+    let swapped = ConnectFour::from_move_list("2445");
+    let mirrored = ConnectFour::from_move_list("4436");
+
+    assert!(game != swapped);
+    assert!(game.same_pattern(&swapped));
+    assert!(swapped.same_pattern(&game));
+    assert!(game.same_pattern(&game));
+    assert!(!game.same_pattern(&mirrored));
+}
+
+#[test]
+fn boards_below_precalculated_depth_need_no_search() {
+    let depth = precalculated_depth() as usize;
+    assert!(depth > 0);
+    let game = ConnectFour::from_move_list(&"4".repeat(depth - 1));
+    let mut solver = Solver::new();
+
+    solver.score(&game);
+
+    assert_eq!(0, solver.nodes());
+}
+
+#[test]
+fn swap_players_after_first_stone() {
+    let original = ConnectFour::from_move_list("4");
+    let mut game = original;
+
+    game.swap_players();
+
+    // The stone belongs to player two now
+    assert_eq!(Some(Player::Two), game.to_grid()[0][3]);
+    // The position is the same, only the names changed, so the score is the same, too.
+    assert_eq!(score(&original), score(&game));
+    // Player one puts in the next stone
+    game.play(Column::from_index(3));
+    assert_eq!(Some(Player::One), game.to_grid()[1][3]);
+}
+
+#[test]
+fn swapped_players_win() {
+    let mut game = ConnectFour::from_move_list("121212");
+    game.swap_players();
+    game.play(Column::from_index(0));
+
+    assert_eq!(Some(Player::Two), game.winner());
+}
+
+#[test]
+fn swapped_players_agree_with_encoding() {
+    let original = ConnectFour::from_move_list("4");
+    let mut swapped = original;
+    swapped.swap_players();
+    let hash = |game: &ConnectFour| {
+        let mut hasher = DefaultHasher::new();
+        game.hash(&mut hasher);
+        hasher.finish()
+    };
+
+    // Only the names of the players differ, not the position.
+    assert_eq!(original.encode(), swapped.encode());
+    assert!(original == swapped);
+    assert_eq!(hash(&original), hash(&swapped));
+    assert!(ConnectFour::from_key(swapped.encode()) == Ok(swapped));
+}
+
+#[test]
+fn builder_keeps_swapped_players() {
+    let mut game = ConnectFour::from_move_list("443");
+    game.swap_players();
+
+    let built = ConnectFourBuilder::new()
+        .cell(0, 3, Some(Player::Two))
+        .cell(1, 3, Some(Player::One))
+        .cell(0, 2, Some(Player::Two))
+        .build()
+        .unwrap();
+
+    assert_eq!(game.to_grid(), built.to_grid());
+    assert_eq!(game.to_grid(), ConnectFour::from_grid(&game.to_grid()).unwrap().to_grid());
+    // Player one moves next, just like in the swapped game.
+    let mut built = built;
+    built.play(Column::from_index(0));
+    assert_eq!(Some(Player::One), built.to_grid()[0][0]);
+}
+
+#[test]
+fn small_board() {
+    type Small = Board<5, 4>;
+    let mut game = Small::new();
+
+    assert_eq!(5, game.legal_moves().count());
+    assert!(!game.is_legal_move(Column::from_index(5)));
+    assert_eq!(Err(PlayError::NoSuchColumn), game.try_play(Column::from_index(5)));
+
+    // Fill the leftmost column. Four stones, but alternating, so nobody wins.
+    game = Small::from_move_list("1111");
+    assert!(!game.is_legal_move(Column::from_index(0)));
+    assert!(!game.is_victory());
+
+    // Horizontal four in a row in the bottom row
+    let game = Small::from_move_list("1122334");
+    assert_eq!(Some(Player::One), game.winner());
+
+    // Diagonal
+    let game = Small::from_move_list("12233434454");
+    assert!(game.is_victory());
+}
+
+#[test]
+fn small_board_without_winner_is_draw() {
+    // 5 * 4 = 20 stones fill the board.
+    let game = Board::<5, 4>::from_move_list("15113343553242445221");
+
+    assert_eq!(20, game.stones());
+    assert!(game.is_draw());
+    assert!(game.is_over());
+}
+
+#[test]
+fn large_board() {
+    type Large = Board<8, 7>;
+
+    let game = Large::from_move_list("8787878");
+    assert!(game.is_victory());
+    assert_eq!(Some(Player::One), game.winner());
+
+    // Three stones of player one at the top of the first column and one at the bottom of the second
+    // are adjacent in the bitboard, but not on the board.
+    let game = Large::from_move_list("51221121321712221");
+    assert_eq!(Some(Player::One), game.to_grid()[6][0]);
+    assert_eq!(Some(Player::One), game.to_grid()[0][1]);
+    assert!(!game.is_victory());
+    assert_eq!(7, game.to_grid().len());
+    assert_eq!(8, game.to_grid()[0].len());
+}
+
+#[test]
+fn last_stone_completes_two_overlapping_lines() {
+    // Player one fills the gap in the middle of five stones in the bottom row.
+    let game = ConnectFour::from_move_list("112244553");
+
+    assert_eq!(
+        vec![
+            [(0, 0), (0, 1), (0, 2), (0, 3)],
+            [(0, 1), (0, 2), (0, 3), (0, 4)]
+        ],
+        game.all_winning_lines()
+    );
+    assert!(ConnectFour::from_move_list("11224455").all_winning_lines().is_empty());
+}
+
+#[test]
+fn build_position_cell_by_cell() {
+    let game = ConnectFourBuilder::new()
+        .cell(0, 3, Some(Player::One))
+        .cell(1, 3, Some(Player::Two))
+        .cell(0, 4, Some(Player::One))
+        .build()
+        .unwrap();
+
+    assert!(ConnectFour::from_move_list("445") == game);
+}
+
+#[test]
+fn built_position_may_be_unreachable() {
+    // Player one has three stones more than player two, which can not happen in a real game.
+    let game = ConnectFourBuilder::new()
+        .cell(0, 0, Some(Player::One))
+        .cell(0, 1, Some(Player::One))
+        .cell(0, 2, Some(Player::One))
+        .build()
+        .unwrap();
+
+    assert_eq!(3, game.stones());
+    assert!(!game.can_win_in_next_move());
+    assert_eq!(Some(Player::One), game.to_grid()[0][2]);
+}
+
+#[test]
+fn score_unreachable_built_position() {
+    // Too few stones to ask for a search, but not among the precalculated scores either. Player
+    // one threatens to complete the bottom row on both ends.
+    let game = ConnectFourBuilder::new()
+        .cell(0, 1, Some(Player::One))
+        .cell(0, 2, Some(Player::One))
+        .cell(0, 3, Some(Player::One))
+        .build()
+        .unwrap();
+
+    let score = Solver::new().score(&game);
+
+    assert_eq!(-19, score);
+}
+
+#[test]
+fn floating_stones_are_rejected() {
+    let result = ConnectFourBuilder::new()
+        .cell(0, 3, Some(Player::One))
+        .cell(2, 3, Some(Player::Two))
+        .build();
+
+    assert_eq!(
+        BuildError::FloatingStone { row: 2, column: 3 },
+        result.err().unwrap()
+    );
+}
+
+#[test]
+fn bitmasks_of_possible_and_winning_positions() {
+    // Player one has three stones in the bottom row, player two three on top of them.
+    let game = ConnectFour::from_move_list("445566");
+
+    let cell = |row: u32, column: u32| 1u64 << (7 * column + row);
+    let expected_possible =
+        cell(0, 0) | cell(0, 1) | cell(0, 2) | cell(2, 3) | cell(2, 4) | cell(2, 5) | cell(0, 6);
+    assert_eq!(expected_possible, game.possible_mask());
+    assert_eq!(cell(0, 2) | cell(0, 6), game.winning_positions());
+    assert_eq!(cell(1, 2) | cell(1, 6), game.opponent_winning_positions());
+    assert_eq!(
+        (cell(0, 2) | cell(0, 6), cell(1, 2) | cell(1, 6)),
+        game.threat_masks()
+    );
+
+    // Player two took the cell to the left of the three stones of player one.
+    let game = ConnectFour::from_move_list("445563");
+    assert_eq!(cell(0, 2) | cell(0, 6), game.winning_positions());
+    assert_eq!((cell(0, 6), 0), game.threat_masks());
+}
+
+#[test]
+fn pop_bottom_stone() {
+    let (one, two) = (Some(Player::One), Some(Player::Two));
+    // Player one to move. Column one holds a stone of player one below one of player two.
+    let mut game = ConnectFour::from_move_list("1123");
+
+    // Bottom stone of column three belongs to player two, column four is empty.
+    assert!(!game.pop(Column::from_index(2)));
+    assert!(!game.pop(Column::from_index(3)));
+    assert!(game.pop(Column::from_index(0)));
+
+    let grid = game.to_grid();
+    assert_eq!([two, one, two], grid[0][..3]);
+    assert_eq!(None, grid[1][0]);
+    assert_eq!(3, game.stones());
+
+    // Now it is player two's turn, who owns the bottom stone of column one.
+    assert!(game.pop(Column::from_index(0)));
+    assert!(game.play(Column::from_index(0)));
+    assert_eq!(one, game.to_grid()[0][0]);
+    assert_eq!(3, game.stones());
+}
+
+#[test]
+fn pop_after_the_game_is_over() {
+    // Player one completed the bottom row.
+    let mut game = ConnectFour::from_move_list("1525354");
+    assert!(game.is_over());
+
+    // Player two owns the bottom stone of column five, but the game has already been decided.
+    assert!(!game.pop(Column::from_index(4)));
+    assert!(game.is_over());
+}
+
+#[test]
+fn score_popped_board() {
+    // Player one stacks stones in column seven, while player two fills the bottom row next to it.
+    let mut game = ConnectFour::from_move_list("727374");
+    // Instead of blocking, player one pops. Player two has more stones now than in any real game.
+    assert!(game.pop(Column::from_index(6)));
+    assert_eq!(5, game.stones());
+
+    // Player two completes the bottom row right away.
+    assert_eq!(19, Solver::new().score(&game));
+}
+
+#[test]
+fn count_reachable_positions() {
+    // OEIS A212693
+    let expected = [1, 7, 49, 238, 1120, 4263, 16422, 54859];
+    let counts: Vec<u64> = (0..8)
+        .map(|stones| count_positions(stones, false))
+        .collect();
+    assert_eq!(expected[..], counts);
+
+    // Up to mirroring. Symmetric positions are their own mirror image, so there are slightly more
+    // than half as many.
+    let expected = [1, 4, 25, 121, 568, 2144, 8231, 27473];
+    let counts: Vec<u64> = (0..8).map(|stones| count_positions(stones, true)).collect();
+    assert_eq!(expected[..], counts);
+}
+
+#[test]
+fn winning_moves() {
+    let winning_moves = |moves| {
+        let game = ConnectFour::from_move_list(moves);
+        (0..7)
+            .map(Column::from_index)
+            .filter(|&column| game.is_winning_move(column))
+            .collect::<Vec<_>>()
+    };
+    let columns = |indices: &[u8]| {
+        indices
+            .iter()
+            .copied()
+            .map(Column::from_index)
+            .collect::<Vec<_>>()
+    };
+
+    // Vertical
+    assert_eq!(columns(&[0]), winning_moves("121212"));
+    // Horizontal, on both ends
+    assert_eq!(columns(&[2, 6]), winning_moves("445566"));
+    // Diagonal from bottom left to top right
+    assert_eq!(columns(&[1]), winning_moves("142353341446"));
+    // Diagonal from top left to bottom right
+    assert_eq!(columns(&[2]), winning_moves("61264724334553"));
+    // No threats at all
+    assert_eq!(columns(&[]), winning_moves("44"));
+    // Columns beyond the board
+    let game = ConnectFour::from_move_list("121212");
+    assert!(!game.is_winning_move(Column::from_index(7)));
+    assert!(!game.is_winning_move(Column::from_index(10)));
+}
+
+#[test]
+fn blocking_moves() {
+    let blocking_moves = |moves| {
+        ConnectFour::from_move_list(moves)
+            .blocking_moves()
+            .collect::<Vec<_>>()
+    };
+
+    // Player one threatens to complete the first column.
+    assert_eq!(vec![Column::from_index(0)], blocking_moves("12121"));
+    // Three in a row at the bottom, open on both ends
+    assert_eq!(Vec::<Column>::new(), blocking_moves("44553"));
+    // Nothing to block
+    assert_eq!(Vec::<Column>::new(), blocking_moves("44"));
+}
+
+#[test]
+fn double_threat_of_opponent() {
+    let double_threat = |moves| ConnectFour::from_move_list(moves).opponent_has_double_threat();
+
+    // Three in a row at the bottom, open on both ends
+    assert!(double_threat("44553"));
+    // Only the first column needs to be blocked
+    assert!(!double_threat("12121"));
+    assert!(!double_threat("44"));
+    // Game is over
+    assert!(!double_threat("4455667"));
+}
+
+#[test]
+fn threats_after_move() {
+    let game = ConnectFour::from_move_list("4455");
+
+    // Three in a row at the bottom, open on both ends
+    assert_eq!(Some(2), game.threats_after(Column::from_index(2)));
+    assert_eq!(Some(0), game.threats_after(Column::from_index(0)));
+
+    let game = ConnectFour::from_move_list("111111");
+    assert_eq!(None, game.threats_after(Column::from_index(0)));
+}
+
+#[test]
+fn successor() {
+    let before = ConnectFour::from_move_list("445");
+    let after = ConnectFour::from_move_list("4453");
+
+    assert_eq!(Some(Column::from_index(2)), after.is_successor_of(&before));
+    // Wrong direction
+    assert_eq!(None, before.is_successor_of(&after));
+    // Two moves apart
+    assert_eq!(None, after.is_successor_of(&ConnectFour::from_move_list("44")));
+    // One stone more, but the first stone of player two is in another column.
+    let other = ConnectFour::from_move_list("4353");
+    assert_eq!(None, other.is_successor_of(&before));
+    // No moves after the game is over
+    let won = ConnectFour::from_move_list("4455667");
+    let mut continued = won;
+    continued.play(Column::from_index(0));
+    assert_eq!(None, continued.is_successor_of(&won));
+}
+
+#[test]
+fn heights_of_columns() {
+    let game = ConnectFour::from_move_list("4441117");
+
+    assert_eq!([3, 0, 0, 3, 0, 0, 1], game.heights());
+    assert_eq!([0.; 7], ConnectFour::new().column_fill());
+    let fill = ConnectFour::from_move_list("111444").column_fill();
+    assert_eq!([0.5, 0., 0., 0.5, 0., 0., 0.], fill);
+    assert_eq!(1., ConnectFour::from_move_list("111111").column_fill()[0]);
+}
+
+#[test]
+fn children_skip_full_columns() {
+    let game = ConnectFour::from_move_list("111111");
+
+    let children: Vec<(Column, ConnectFour)> = game.children().collect();
+
+    assert_eq!(6, children.len());
+    for (column, child) in children {
+        assert_ne!(Column::from_index(0), column);
+        let mut expected = game;
+        expected.play(column);
+        assert!(expected == child);
+    }
+}
+
+#[test]
+fn forks() {
+    let game = ConnectFour::from_move_list("4455");
+
+    // Three in a row at the bottom, open on both ends
+    assert!(game.creates_fork(Column::from_index(2)));
+    assert!(game.creates_fork(Column::from_index(5)));
+    // Only one open end
+    assert!(!ConnectFour::from_move_list("445516").creates_fork(Column::from_index(2)));
+    assert!(!game.creates_fork(Column::from_index(0)));
+    // Wins right away
+    assert!(!ConnectFour::from_move_list("445566").creates_fork(Column::from_index(2)));
+    // Illegal
+    assert!(!ConnectFour::from_move_list("111111").creates_fork(Column::from_index(0)));
+}
+
+#[test]
+fn heuristic_counts_open_cells_completing_a_line() {
+    assert_eq!(0, ConnectFour::new().heuristic());
+    // Player one has three in a row at the bottom, open on both ends.
+    assert_eq!(2, ConnectFour::from_move_list("44553").heuristic());
+    // Two stones in a row are no threat yet.
+    assert_eq!(0, ConnectFour::from_move_list("445").heuristic());
+    // Three in a row, but the left end is blocked by player two. The three stones of player two in
+    // the first column do not count, since player one put in the last stone.
+    assert_eq!(1, ConnectFour::from_move_list("4121317").heuristic());
+}
+
+#[test]
+fn full_board_without_winner_is_draw() {
+    // |O|X|O|X|X|O|X|
+    // |X|O|O|X|O|O|X|
+    // |X|X|X|O|X|X|O|
+    // |O|O|X|O|X|O|O|
+    // |O|X|O|O|X|X|X|
+    // |O|O|O|X|O|X|X|
+    // ---------------
+    //  1 2 3 4 5 6 7
+    let game = ConnectFour::from_move_list("656173566152215676422337377473141445425321");
+
+    assert!(game.is_over());
+    assert!(game.is_draw());
+}
+
+#[test]
+fn victory_is_not_a_draw() {
+    let game = ConnectFour::from_move_list("5655663642443");
+
+    assert!(game.is_over());
+    assert!(!game.is_draw());
+}
+
+#[test]
+fn game_in_progress_is_no_draw() {
+    let game = ConnectFour::from_move_list("253733227554644");
+
+    assert!(!game.is_over());
+    assert!(!game.is_draw());
+}
+
+#[test]
+fn winner_of_finished_and_running_games() {
+    assert_eq!(
+        Some(Player::One),
+        ConnectFour::from_move_list("5655663642443").winner()
+    );
+    // Player two completes the second column
+    assert_eq!(
+        Some(Player::Two),
+        ConnectFour::from_move_list("12121232").winner()
+    );
+    assert_eq!(None, ConnectFour::from_move_list("253733227554644").winner());
+    assert_eq!(
+        None,
+        ConnectFour::from_move_list("656173566152215676422337377473141445425321").winner()
+    );
+}
+
 #[test]
 fn can_not_win_with_next_move() {
     // |X|O|O|O|X|O| |
@@ -71,6 +1288,89 @@ fn can_win_with_next_move() {
     assert!(game.can_win_in_next_move())
 }
 
+#[test]
+fn no_loosing_moves_on_empty_board() {
+    let game = ConnectFour::new();
+
+    assert_eq!(0, game.loosing_moves().count());
+}
+
+#[test]
+#[should_panic]
+fn no_loosing_moves_if_current_player_can_win() {
+    let game = ConnectFour::from_move_list("253733227554644");
+
+    let _ = game.loosing_moves();
+}
+
+#[test]
+fn loosing_moves_if_opponent_must_be_blocked() {
+    // Player one has three stones in the first column. Every move but blocking it looses.
+    let game = ConnectFour::from_move_list("12121");
+
+    let loosing: Vec<_> = game.loosing_moves().collect();
+
+    assert_eq!((1..7).map(Column::from_index).collect::<Vec<_>>(), loosing);
+}
+
+#[test]
+fn loosing_moves_allow_opponent_to_win_next() {
+    for entry in read_dataset("./tests/Test_L3_R1").unwrap() {
+        let (_, game, _) = entry.unwrap();
+        if game.can_win_in_next_move() {
+            continue;
+        }
+
+        let expected: Vec<_> = game
+            .legal_moves()
+            .filter(|&column| {
+                let mut next = game;
+                next.play(column);
+                next.can_win_in_next_move()
+            })
+            .collect();
+
+        assert_eq!(expected, game.loosing_moves().collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn forced_move_blocks_opponent() {
+    // Player one has three stones in the first column, which must be blocked.
+    let game = ConnectFour::from_move_list("12121");
+
+    assert_eq!(Some(Column::from_index(0)), game.forced_move());
+}
+
+#[test]
+fn no_forced_move_with_several_options() {
+    assert_eq!(None, ConnectFour::new().forced_move());
+}
+
+#[test]
+fn no_forced_move_if_current_player_can_win() {
+    let game = ConnectFour::from_move_list("253733227554644");
+
+    assert_eq!(None, game.forced_move());
+}
+
+#[test]
+fn no_forced_move_against_double_threat() {
+    // | | | | | | | |
+    // | | | | | | | |
+    // | | | | | | | |
+    // | | | | | | | |
+    // | |O|O| | | | |
+    // | |X|X|X| | | |
+    // ---------------
+    //  1 2 3 4 5 6 7
+    let game = ConnectFour::from_move_list("22334");
+
+    // Player one wins in the first or fifth column, whatever player two does.
+    assert_eq!(7, game.loosing_moves().count());
+    assert_eq!(None, game.forced_move());
+}
+
 #[test]
 fn score_depth_one_victory() {
     // |X|O|O|O|X|O| |
@@ -114,6 +1414,112 @@ fn score_depth_four_victory() {
     assert_eq!(-1, score(&game))
 }
 
+#[test]
+fn dead_board() {
+    // Four cells are left, but each line still open to one player is blocked by the other.
+    let dead = ConnectFour::from_move_list("27115645234734562267466315427532353641");
+    assert!(dead.is_dead());
+    assert!(!dead.is_over());
+    // Scored without a search
+    let mut solver = Solver::new();
+    assert_eq!(0, solver.score(&dead));
+    assert_eq!(0, solver.nodes());
+
+    // One stone less, and the last line is still open.
+    assert!(!ConnectFour::from_move_list("2711564523473456226746631542753235364").is_dead());
+    assert!(!ConnectFour::new().is_dead());
+    // Full board without a winner
+    assert!(ConnectFour::from_move_list("547125662261271266215743771576315353334444").is_dead());
+}
+
+#[test]
+fn dead_cells() {
+    // . . . . O . .
+    // . . . . X O .
+    // . . . . X X X
+    // . . . . O O O
+    // . . . . X O X
+    // X O X X O X O
+    let game = ConnectFour::from_move_list("6555471652777655663");
+
+    // Lines through the empty cell on top of the last column are blocked for both players.
+    assert!(game.is_dead_cell(4, 6));
+    assert!(!game.is_dead_cell(5, 6));
+    assert!(!game.is_dead_cell(5, 0));
+    // Stones may be part of lines still open.
+    assert!(!game.is_dead_cell(3, 4));
+    assert!((0..6).all(|row| (0..7).all(|column| !ConnectFour::new().is_dead_cell(row, column))));
+}
+
+#[test]
+fn quick_eval_favours_winning_side() {
+    assert_eq!(0, ConnectFour::new().quick_eval());
+    // Player one took the center
+    assert!(ConnectFour::from_move_list("4").quick_eval() < 0);
+    // Player one wins with the next stone
+    assert!(ConnectFour::from_move_list("445566").quick_eval() > 0);
+    // Player two can only block one end of the open three
+    assert!(ConnectFour::from_move_list("44553").quick_eval() < 0);
+    // Player one has already won
+    assert!(ConnectFour::from_move_list("4455667").quick_eval() < 0);
+    // Player one has three in a row with one open end. Player two can block it, but not for free.
+    assert!(ConnectFour::from_move_list("4455631").quick_eval() < 0);
+}
+
+#[test]
+fn phase_of_test_sets() {
+    assert_eq!(Phase::Opening, ConnectFour::new().phase());
+    // First lines of the test sets for the beginning, middle and end of a game
+    assert_eq!(Phase::Opening, ConnectFour::from_move_list("32164625").phase());
+    assert_eq!(
+        Phase::Midgame,
+        ConnectFour::from_move_list("5554224333234511764415115").phase()
+    );
+    assert_eq!(
+        Phase::Endgame,
+        ConnectFour::from_move_list("2252576253462244111563365343671351441").phase()
+    );
+}
+
+#[test]
+fn score_bounds_contain_score() {
+    assert_eq!((-21, 21), ConnectFour::new().score_bounds());
+    // Win with the next stone is the upper bound
+    let game = ConnectFour::from_move_list("445566");
+    assert_eq!((-18, 18), game.score_bounds());
+    assert_eq!(18, score(&game));
+    // Game is already over
+    assert_eq!((-18, -18), ConnectFour::from_move_list("4455667").score_bounds());
+}
+
+#[test]
+fn interpret_score_of_endgame() {
+    // |X|O|O|O|X| | |
+    // |O|X|O|X|X|X| |
+    // |X|O|O|X|O|O| |
+    // |X|O|X|O|X|X| |
+    // |O|O|O|X|X|O|O|
+    // |X|X|O|X|X|X|O|
+    // ---------------
+    //  1 2 3 4 5 6 7
+    let game = ConnectFour::from_move_list("2252576253462244111563365343671351441");
+
+    // Opponent wins with the 39th stone
+    assert_eq!((Outcome::Loss, 2), interpret_score(&game, -2));
+    // Opponent wins with the 41st stone
+    assert_eq!((Outcome::Loss, 4), interpret_score(&game, -1));
+    // Current player wins with the 42nd stone
+    assert_eq!((Outcome::Win, 5), interpret_score(&game, 1));
+    assert_eq!((Outcome::Draw, 5), interpret_score(&game, 0));
+}
+
+#[test]
+fn interpret_score_of_immediate_win() {
+    let game = ConnectFour::from_move_list("2252576253462244111563365343671351441677");
+
+    assert_eq!((Outcome::Win, 1), interpret_score(&game, score(&game)));
+}
+
 #[test]
 fn score_begin_easy() {
     let game = ConnectFour::from_move_list("253733227554");
@@ -165,14 +1571,58 @@ fn thousand_begin_easy() {
     verify_test_data(test_data);
 }
 
+#[test]
+fn thousand_end_easy_mtdf() {
+    let test_data = "./tests/Test_L3_R1";
+    // MTD(f) must yield the same scores as the bisection used by `score`.
+    verify_test_data_with(test_data, |game| Solver::new().score_mtdf(game));
+}
+
+#[test]
+fn verify_dataset_reports_mismatches() {
+    let mut solver = Solver::new();
+    assert!(verify_dataset("./tests/Test_L3_R1", &mut solver)
+        .unwrap()
+        .is_empty());
+
+    let path = env::temp_dir().join("connect_four_solver_verify_dataset.txt");
+    fs::write(&path, "2252576253462244111563365343671351441 -1\n\n44 5\n").unwrap();
+    let mismatches = verify_dataset(&path, &mut solver).unwrap();
+    fs::write(&path, "44 five\n").unwrap();
+    let invalid = verify_dataset(&path, &mut solver);
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(vec![("44".to_owned(), 5, 1)], mismatches);
+    assert_eq!(io::ErrorKind::InvalidData, invalid.unwrap_err().kind());
+}
+
+/// Scores each position of the dataset and its mirror image. Both must match the expected score.
 fn verify_test_data(test_data: &str) {
-    let input = BufReader::new(File::open(test_data).unwrap());
+    for entry in read_dataset(test_data).unwrap() {
+        let (moves, game, expected_score) = entry.unwrap();
+
+        let actual_score = score(&game);
+
+        assert_eq!(expected_score, actual_score, "{moves}");
+        assert_mirror_symmetry(&game, &mut Solver::new());
+    }
+}
+
+/// Mirrored positions must have the same score. A difference would point to a bug in the bitboard
+/// logic, since the moves of the search are not symmetric. `solver` only scores the mirror image, so
+/// its table does not hold any results of the search for `game`.
+fn assert_mirror_symmetry(game: &ConnectFour, solver: &mut Solver) {
+    let mirrored = game.mirror();
+    assert_eq!(
+        score(game),
+        solver.score(&mirrored),
+        "Mirror images score differently:\n{game}\n{mirrored}"
+    );
+}
 
-    for line in input.lines() {
-        let line = line.unwrap();
-        let mut line_it = line.split_whitespace();
-        let game = ConnectFour::from_move_list(line_it.next().unwrap());
-        let expected_score: i8 = line_it.next().unwrap().parse().unwrap();
+fn verify_test_data_with(test_data: &str, score: impl Fn(&ConnectFour) -> i8) {
+    for entry in read_dataset(test_data).unwrap() {
+        let (_, game, expected_score) = entry.unwrap();
 
         let actual_score = score(&game);
 